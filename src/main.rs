@@ -1,15 +1,47 @@
 mod misc;
 use ui::*;
 mod ui;
-use misc::*;
+use misc::{wgsl::create_shader_module, *};
 mod updates;
-use updates::*;
+use updates::{
+    comparison::comparison_panes, hot_reload::initial_shader_sources, recorder::toggle_recording, *,
+};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Instant,
+};
 
 use nannou::{prelude::*, wgpu::Device};
 use nannou_egui::Egui;
 
 const NUM_BOIDS: usize = 255;
 const NUM_PREDATORS: usize = 4;
+const NUM_FOOD_PATCHES: usize = 6;
+const LEADER_ID: usize = 0;
+
+// World bounds the boids and predators turn back at
+const MAX_SCREEN_X: f32 = 150.0;
+const MIN_SCREEN_X: f32 = -150.0;
+const MAX_SCREEN_Y: f32 = 70.0;
+const MIN_SCREEN_Y: f32 = -70.0;
+
+// Camera zoom the window opens with, centred on the world origin, and double clicks go back to
+const INITIAL_ZOOM: f32 = 0.01;
+
+// Time advanced per simulation step while frames are captured, so the output doesn't depend on
+// the frame rate it was captured at
+const SEQUENCE_STEP_TIME: f32 = 1.0 / 60.0;
+
+// Cells of the density heatmap grid, which covers the world bounds plus DENSITY_MARGIN
+const DENSITY_GRID_X: usize = 192;
+const DENSITY_GRID_Y: usize = 96;
+const DENSITY_MARGIN: f32 = 20.0;
+
+// Trail intensities need more precision than the frame to fade smoothly
+const TRAIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+// Bloom buffers, float so the bright pass keeps values above 1
+const POST_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
 
 fn vertices_as_bytes(data: &[Vertex]) -> &[u8] {
     unsafe { wgpu::bytes::from_slice(data) }
@@ -19,11 +51,41 @@ fn main() {
     nannou::app(model).update(update).run();
 }
 
+// Both windows request the same descriptor so they share a single device
+fn device_descriptor() -> wgpu::DeviceDescriptor<'static> {
+    wgpu::DeviceDescriptor {
+        label: Some("Boids Device"),
+        features: wgpu::Features::empty(),
+        limits: wgpu::Limits {
+            // The compute bind group holds more storage buffers than the default of 8
+            max_storage_buffers_per_shader_stage: 16,
+            ..Default::default()
+        },
+    }
+}
+
+fn initial_food_patches(max_food: f32) -> [FoodPatch; NUM_FOOD_PATCHES] {
+    let mut patches = [FoodPatch {
+        pos: [0.0, 0.0],
+        amount: max_food,
+        radius: 8.0,
+    }; NUM_FOOD_PATCHES];
+
+    // Spread the patches evenly around an ellipse inside the screen edges
+    for (i, patch) in patches.iter_mut().enumerate() {
+        let angle = (i as f32 / NUM_FOOD_PATCHES as f32) * std::f32::consts::TAU;
+        patch.pos = [110.0 * angle.cos(), 50.0 * angle.sin()];
+    }
+
+    patches
+}
+
 fn model(app: &App) -> Model {
     let ui_wid = app
         .new_window()
         .title("ui-controls")
         .size(1366, 768)
+        .device_descriptor(device_descriptor())
         .view(ui_view)
         .raw_event(raw_ui_event)
         .build()
@@ -32,7 +94,13 @@ fn model(app: &App) -> Model {
     let main_wid = app
         .new_window()
         .size(1366, 768)
+        .device_descriptor(device_descriptor())
         .view(view)
+        .key_pressed(key_pressed)
+        .mouse_pressed(mouse_pressed)
+        .mouse_released(mouse_released)
+        .mouse_moved(mouse_moved)
+        .mouse_wheel(mouse_wheel)
         //.raw_event(raw_ui_event)
         .build()
         .unwrap();
@@ -45,10 +113,81 @@ fn model(app: &App) -> Model {
     let view_params = ViewParams {
         x_shift: 0.0,
         y_shift: 0.0,
-        zoom: 0.01,
+        zoom: INITIAL_ZOOM,
         time_modifier: 0.01,
     };
 
+    let glyph_params = GlyphParams {
+        boid_size: 1.2,
+        predator_size: 3.0,
+        boid_shape: GlyphShape::Fish as u32,
+        predator_shape: GlyphShape::Arrow as u32,
+        units: GlyphUnits::World as u32,
+    };
+
+    let trail_params = TrailParams {
+        boid_color: [0.35, 0.45, 0.8, 1.0],
+        predator_color: [0.9, 0.25, 0.2, 1.0],
+        boid_length: 40.0,
+        predator_length: 150.0,
+        enabled: 0,
+        _padding: 0.0,
+    };
+
+    // Everything off, which looks the same as rendering straight into the frame
+    let post_params = PostParams {
+        bloom: 0,
+        bloom_threshold: 1.0,
+        bloom_intensity: 0.6,
+        bloom_radius: 1.5,
+        tone_mapping: ToneMapping::Off as u32,
+        exposure: 0.0,
+        vignette: 0,
+        vignette_strength: 0.5,
+        vignette_radius: 0.4,
+    };
+
+    let color_map = ColorMap::Rainbow;
+    let color_params = ColorParams {
+        offset: color_map.coefficients()[0],
+        amplitude: color_map.coefficients()[1],
+        frequency: color_map.coefficients()[2],
+        phase: color_map.coefficients()[3],
+        mode: ColorMode::Flat as u32,
+        min_value: 0.0,
+        max_value: 1.0,
+        _padding: 0.0,
+    };
+
+    let overlay_params = OverlayParams {
+        predator_ranges: 0,
+        pursuit_lines: 0,
+        strategy_targets: 0,
+        boid_ranges: 0,
+        boid_id: 1,
+        line_width: 1.5,
+        selection: SelectionKind::None as u32,
+        selected_id: 0,
+    };
+
+    let grid_params = GridParams {
+        grid: 0,
+        axes: 0,
+        boundary: 0,
+        scale_bar: 0,
+        min_spacing: 40.0,
+        opacity: 0.35,
+    };
+
+    let density_params = DensityParams {
+        mode: DensityMode::Off as u32,
+        kernel_radius: 3.0,
+        average_frames: 3000.0,
+        show_average: 0,
+        max_density: 4.0,
+        opacity: 0.7,
+    };
+
     let boid_params = BoidParams {
         max_velocity: 0.36400002,
         min_velocity: -0.35600003,
@@ -59,6 +198,17 @@ fn model(app: &App) -> Model {
         self_avoid_factor: 0.013000003,
         predator_avoid_factor: 0.01000003,
         matching_factor: 0.038800016,
+        alarm_spread: 0.9,
+        alarm_delay: 4.0,
+        alarm_decay: 0.97,
+        alarm_speed_boost: 0.6,
+        alarm_separation_boost: 0.5,
+        min_speed: 0.15,
+        max_speed: 0.36,
+        max_force: 0.05,
+        limit_mode: SpeedLimitMode::Magnitude as u32,
+        wander_amplitude: 0.004,
+        wander_correlation: 30.0,
     };
 
     let predator_params = PredatorParams {
@@ -72,28 +222,59 @@ fn model(app: &App) -> Model {
         visual_range: 100.0,
         protected_range: 20.0,
         interest_range: 50.0,
+        min_speed: 1.0,
+        max_speed: 4.6,
+        max_force: 1.0,
+        limit_mode: SpeedLimitMode::Magnitude as u32,
+    };
+
+    let food_params = FoodParams {
+        hunger_rate: 0.0015,
+        hunger_threshold: 0.5,
+        foraging_factor: 0.004,
+        eat_rate: 0.02,
+        regrow_rate: 0.002,
+        max_food: 1.0,
+    };
+
+    let leader = LeaderControl {
+        target_pos: [1.0, 0.0],
+        influence: 25.0,
+        mode: LeaderMode::Off as u32,
+    };
+
+    let seed: SeedUniform = 0;
+
+    let init_params = InitParams {
+        boid_formation: BoidFormation::Rectangle as u32,
+        predator_placement: PredatorPlacement::Random as u32,
+        num_clusters: 4,
+        spread: 40.0,
     };
 
     let kcm = KeyboardControlMode::View;
 
     let device = window.device();
-    let win_size = window.inner_size_points();
-    let format = Frame::TEXTURE_FORMAT;
+    let (width, height) = window.inner_size_pixels();
+    let resolution = ResolutionUniform {
+        xy: [width as f32, height as f32],
+        origin: [0.0, 0.0],
+        scale_factor: window.scale_factor(),
+        _padding: 0.0,
+    };
     let sample_count = window.msaa_samples();
 
-    let vs_desc = wgpu::include_wgsl!("./shaders/vertex/v2.wgsl");
-    let fs_desc = wgpu::include_wgsl!("./shaders/fragment/boids_frag.wgsl");
-    let vs_mod = device.create_shader_module(vs_desc);
-    let fs_mod = device.create_shader_module(fs_desc);
+    let vs_mod = create_shader_module(device, "vertex/v2.wgsl");
+    let fs_mod = create_shader_module(device, "fragment/boids_frag.wgsl");
+    let trail_mod = create_shader_module(device, "fragment/trails.wgsl");
+    let post_mod = create_shader_module(device, "fragment/post.wgsl");
 
-    let boid_pos_desc = wgpu::include_wgsl!("./shaders/compute/init_boids.wgsl");
-    let boid_sac_desc = wgpu::include_wgsl!("./shaders/compute/seperation_alignment_cohesion.wgsl");
-    let pred_pos_desc = wgpu::include_wgsl!("./shaders/compute/init_predator.wgsl");
-    let pred_pursuit_desc = wgpu::include_wgsl!("./shaders/compute/predator_chase_path.wgsl");
-    let boid_pos_mod = device.create_shader_module(boid_pos_desc);
-    let boid_sac_mod = device.create_shader_module(boid_sac_desc);
-    let pred_pos_mod = device.create_shader_module(pred_pos_desc);
-    let pred_pursuit_mod = device.create_shader_module(pred_pursuit_desc);
+    let boid_pos_mod = create_shader_module(device, "compute/init_boids.wgsl");
+    let boid_sac_mod = create_shader_module(device, "compute/seperation_alignment_cohesion.wgsl");
+    let pred_pos_mod = create_shader_module(device, "compute/init_predator.wgsl");
+    let pred_pursuit_mod = create_shader_module(device, "compute/predator_chase_path.wgsl");
+    let forage_mod = create_shader_module(device, "compute/forage.wgsl");
+    let density_mod = create_shader_module(device, "compute/density.wgsl");
 
     let vertices_bytes = vertices_as_bytes(&VERTICES[..]);
     let vertex_buf = device.create_buffer_init(&BufferInitDescriptor {
@@ -102,132 +283,182 @@ fn model(app: &App) -> Model {
         usage: wgpu::BufferUsages::VERTEX,
     });
 
-    let boids_pos_buf = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Boids Positions Buffer"),
-        size: (std::mem::size_of::<[Boid; NUM_BOIDS]>()) as wgpu::BufferAddress,
-        usage: wgpu::BufferUsages::STORAGE
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
+    let layouts = get_bind_group_layouts(device);
 
-    let predator_pos_buf = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Predator Positions Buffer"),
-        size: (std::mem::size_of::<[Boid; NUM_PREDATORS]>()) as wgpu::BufferAddress,
-        usage: wgpu::BufferUsages::STORAGE
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
+    let render_pipeline = create_render_pipeline(
+        device,
+        &layouts,
+        &vs_mod,
+        &fs_mod,
+        Frame::TEXTURE_FORMAT,
+        sample_count,
+    );
 
-    let pursuits_buf = device.create_buffer_init(&BufferInitDescriptor {
-        label: Some("Pursuits IDs Buffer"),
-        contents: unsafe { wgpu::bytes::from_slice(&[0xFFFFFFFFu32; NUM_PREDATORS]) },
-        usage: wgpu::BufferUsages::STORAGE
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::COPY_DST,
-    });
+    let trail_pipeline =
+        create_render_pipeline(device, &layouts, &vs_mod, &trail_mod, TRAIL_FORMAT, 1);
+
+    let post_bright_pipeline =
+        create_post_pipeline(device, &layouts, &post_mod, "bright_pass", POST_FORMAT, 1);
+    let post_blur_h_pipeline = create_post_pipeline(
+        device,
+        &layouts,
+        &post_mod,
+        "blur_horizontal",
+        POST_FORMAT,
+        1,
+    );
+    let post_blur_v_pipeline =
+        create_post_pipeline(device, &layouts, &post_mod, "blur_vertical", POST_FORMAT, 1);
+    let post_composite_pipeline = create_post_pipeline(
+        device,
+        &layouts,
+        &post_mod,
+        "composite",
+        Frame::TEXTURE_FORMAT,
+        sample_count,
+    );
 
-    let captures_buf = device.create_buffer_init(&BufferInitDescriptor {
-        label: Some("Predator Capture Count Buffer"),
-        contents: unsafe { wgpu::bytes::from_slice(&[0u32; NUM_PREDATORS]) },
-        usage: wgpu::BufferUsages::STORAGE
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::COPY_DST,
-    });
+    let compute_boid_pos_pipeline = create_compute_pipeline(
+        device,
+        &layouts,
+        "Compute Boid Position Pipeline",
+        &boid_pos_mod,
+        "compute_boid_positions",
+    );
 
-    let captured_boids_buf = device.create_buffer_init(&BufferInitDescriptor {
-        label: Some("Captured Boid IDs Buffer"),
-        contents: unsafe { wgpu::bytes::from_slice(&[1.0f32; NUM_BOIDS]) },
-        usage: wgpu::BufferUsages::STORAGE
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::COPY_DST,
-    });
+    let compute_predator_pos_pipeline = create_compute_pipeline(
+        device,
+        &layouts,
+        "Compute Predator Position Pipeline",
+        &pred_pos_mod,
+        "compute_predator_position",
+    );
 
-    let cpu_read_boids_pos_buf = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("CPU Readable Buffer - Boids"),
-        size: (std::mem::size_of::<[Boid; NUM_BOIDS]>()) as wgpu::BufferAddress,
-        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+    let compute_alarm_pipeline = create_compute_pipeline(
+        device,
+        &layouts,
+        "Compute Alarm Pipeline",
+        &boid_sac_mod,
+        "spread_alarm",
+    );
+
+    let compute_sac_pipeline = create_compute_pipeline(
+        device,
+        &layouts,
+        "Compute SAC Pipeline",
+        &boid_sac_mod,
+        "sac",
+    );
+
+    let compute_pursuit_curve_pipeline = create_compute_pipeline(
+        device,
+        &layouts,
+        "Compute Predator Pursuit Pipeline",
+        &pred_pursuit_mod,
+        "compute_predator_pursuit",
+    );
+
+    let compute_forage_pipeline = create_compute_pipeline(
+        device,
+        &layouts,
+        "Compute Forage Pipeline",
+        &forage_mod,
+        "forage",
+    );
+
+    let compute_food_pipeline = create_compute_pipeline(
+        device,
+        &layouts,
+        "Compute Food Regrowth Pipeline",
+        &forage_mod,
+        "regrow_food",
+    );
+
+    let compute_density_pipeline = create_compute_pipeline(
+        device,
+        &layouts,
+        "Compute Density Pipeline",
+        &density_mod,
+        "splat_density",
+    );
+
+    let time_uniform = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Time Uniform Buffer"),
+        size: std::mem::size_of::<f32>() as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         mapped_at_creation: false,
     });
 
-    let cpu_read_predators_pos_buf = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("CPU Readable Buffer - Predators"),
-        size: (std::mem::size_of::<[Boid; NUM_PREDATORS]>()) as wgpu::BufferAddress,
-        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-        mapped_at_creation: false,
+    let seed_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Seed Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[seed]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     });
 
-    let cpu_read_predators_pursuits_buf = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("CPU Readable Buffer - Predators Current Target Boid IDs"),
-        size: (std::mem::size_of::<[u32; NUM_PREDATORS]>()) as wgpu::BufferAddress,
-        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-        mapped_at_creation: false,
+    let init_params_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Init Parameters Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[init_params]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     });
 
-    let cpu_read_predators_captures_buf = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("CPU Readable Buffer - Predators Current Target Boid IDs"),
-        size: (std::mem::size_of::<[u32; NUM_PREDATORS]>()) as wgpu::BufferAddress,
-        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-        mapped_at_creation: false,
+    let resolution_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Resolution Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[resolution]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     });
 
-    let layouts = get_bind_group_layouts(device);
+    let view_params_storage = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Parameters Storage Buffer"),
+        contents: bytemuck::cast_slice(&[
+            view_params.x_shift,
+            view_params.y_shift,
+            view_params.zoom,
+            view_params.time_modifier,
+        ]),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    });
 
-    let render_pipeline =
-        wgpu::RenderPipelineBuilder::from_layout(&layouts.render_pipeline_layout, &vs_mod)
-            .fragment_shader(&fs_mod)
-            .color_format(format)
-            .add_vertex_buffer::<Vertex>(&wgpu::vertex_attr_array![0 => Float32x2])
-            .sample_count(sample_count)
-            .build(device);
-
-    let compute_boid_pos_pipeline =
-        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Compute Boid Position Pipeline"),
-            layout: Some(&layouts.compute_pipeline_layout),
-            module: &boid_pos_mod,
-            entry_point: "compute_boid_positions",
-        });
+    let glyph_params_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Glyph Parameters Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[glyph_params]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
 
-    let compute_predator_pos_pipeline =
-        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Compute Predator Position Pipeline"),
-            layout: Some(&layouts.compute_pipeline_layout),
-            module: &pred_pos_mod,
-            entry_point: "compute_predator_position",
-        });
+    let density_params_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Density Parameters Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[density_params]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
 
-    let compute_sac_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-        label: Some("Compute SAC Pipeline"),
-        layout: Some(&layouts.compute_pipeline_layout),
-        module: &boid_sac_mod,
-        entry_point: "sac",
+    let color_params_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Color Parameters Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[color_params]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     });
 
-    let compute_pursuit_curve_pipeline =
-        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Compute Predator Pursuit Pipeline"),
-            layout: Some(&layouts.compute_pipeline_layout),
-            module: &pred_pursuit_mod,
-            entry_point: "compute_predator_pursuit",
-        });
+    let overlay_params_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Overlay Parameters Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[overlay_params]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
 
-    let time_uniform = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Time Uniform Buffer"),
-        size: std::mem::size_of::<f32>() as wgpu::BufferAddress,
+    let grid_params_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Grid Parameters Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[grid_params]),
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
     });
 
-    let resolution_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Resolution Uniform Buffer"),
-        contents: bytemuck::cast_slice(&[win_size.0, win_size.1]),
+    let trail_params_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Trail Parameters Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[trail_params]),
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     });
 
-    let (view_params_storage, boid_params_storage, predator_params_storage) =
-        get_storage_buffers(&device, view_params, boid_params, predator_params);
+    let post_params_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Post Processing Parameters Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[post_params]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
 
     let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         layout: &layouts.uniform_bind_group_layout,
@@ -246,52 +477,70 @@ fn model(app: &App) -> Model {
 
     let variable_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         layout: &layouts.variable_bind_group_layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: view_params_storage.as_entire_binding(),
-        }],
-        label: Some("params_bind_group"),
-    });
-
-    let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        layout: &layouts.compute_bind_group_layout,
         entries: &[
             wgpu::BindGroupEntry {
                 binding: 0,
-                resource: boids_pos_buf.as_entire_binding(),
+                resource: view_params_storage.as_entire_binding(),
             },
             wgpu::BindGroupEntry {
                 binding: 1,
-                resource: boid_params_storage.as_entire_binding(),
+                resource: glyph_params_uniform.as_entire_binding(),
             },
             wgpu::BindGroupEntry {
                 binding: 2,
-                resource: predator_pos_buf.as_entire_binding(),
+                resource: color_params_uniform.as_entire_binding(),
             },
             wgpu::BindGroupEntry {
                 binding: 3,
-                resource: predator_params_storage.as_entire_binding(),
+                resource: overlay_params_uniform.as_entire_binding(),
             },
             wgpu::BindGroupEntry {
                 binding: 4,
-                resource: time_uniform.as_entire_binding(),
-            },
-            wgpu::BindGroupEntry {
-                binding: 5,
-                resource: pursuits_buf.as_entire_binding(),
-            },
-            wgpu::BindGroupEntry {
-                binding: 6,
-                resource: captures_buf.as_entire_binding(),
-            },
-            wgpu::BindGroupEntry {
-                binding: 7,
-                resource: captured_boids_buf.as_entire_binding(),
+                resource: grid_params_uniform.as_entire_binding(),
             },
         ],
-        label: Some("compute_bind_group"),
+        label: Some("params_bind_group"),
     });
 
+    let uniforms = Uniforms {
+        time_uniform,
+        resolution,
+        resolution_uniform,
+        seed_uniform,
+        init_params_uniform,
+        uniform_bind_group,
+    };
+
+    let sim = create_simulation(
+        device,
+        &layouts,
+        &uniforms,
+        &density_params_uniform,
+        SimulationParams {
+            boid_params,
+            predator_params,
+            food_params,
+            leader,
+        },
+    );
+
+    let trail_targets =
+        create_trail_targets(device, &layouts, &trail_params_uniform, [width, height]);
+
+    let post_sampler = wgpu::SamplerBuilder::new()
+        .address_mode(wgpu::AddressMode::ClampToEdge)
+        .mag_filter(wgpu::FilterMode::Linear)
+        .min_filter(wgpu::FilterMode::Linear)
+        .build(device);
+    let post_targets = create_post_targets(
+        device,
+        &layouts,
+        &post_params_uniform,
+        &post_sampler,
+        [width, height],
+        sample_count,
+    );
+
     let queue = window.queue();
 
     let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -303,14 +552,14 @@ fn model(app: &App) -> Model {
             label: Some("Compute Pass - Boids"),
         });
         compute_pass.set_pipeline(&compute_boid_pos_pipeline);
-        compute_pass.set_bind_group(0, &compute_bind_group, &[]);
+        compute_pass.set_bind_group(0, &sim.compute_bind_group, &[]);
         compute_pass.dispatch_workgroups(16, 16, 1);
     }
 
     encoder.copy_buffer_to_buffer(
-        &boids_pos_buf,
+        &sim.buffers.boids_pos_buf,
         0,
-        &cpu_read_boids_pos_buf,
+        &sim.buffers.cpu_read_boids_pos_buf,
         0,
         (std::mem::size_of::<[Boid; NUM_BOIDS]>()) as wgpu::BufferAddress,
     );
@@ -326,14 +575,14 @@ fn model(app: &App) -> Model {
             label: Some("Compute Pass - Predator"),
         });
         compute_pass.set_pipeline(&compute_predator_pos_pipeline);
-        compute_pass.set_bind_group(0, &compute_bind_group, &[]);
+        compute_pass.set_bind_group(0, &sim.compute_bind_group, &[]);
         compute_pass.dispatch_workgroups(4, 1, 1);
     }
 
     encoder.copy_buffer_to_buffer(
-        &predator_pos_buf,
+        &sim.buffers.predator_pos_buf,
         0,
-        &cpu_read_predators_pos_buf,
+        &sim.buffers.cpu_read_predators_pos_buf,
         0,
         (std::mem::size_of::<[Boid; NUM_PREDATORS]>()) as wgpu::BufferAddress,
     );
@@ -343,39 +592,102 @@ fn model(app: &App) -> Model {
     Model {
         pipelines: Pipelines {
             render_pipeline,
+            trail_pipeline,
+            post_bright_pipeline,
+            post_blur_h_pipeline,
+            post_blur_v_pipeline,
+            post_composite_pipeline,
             compute_boid_pos_pipeline,
+            compute_alarm_pipeline,
             compute_sac_pipeline,
             compute_predator_pos_pipeline,
             compute_pursuit_curve_pipeline,
+            compute_forage_pipeline,
+            compute_food_pipeline,
+            compute_density_pipeline,
         },
-        compute_bind_group,
-        buffers: Buffers {
-            vertex_buf,
-            boids_pos_buf,
-            cpu_read_boids_pos_buf,
-            predator_pos_buf,
-            pursuits_buf,
-            captures_buf,
-            captured_boids_buf,
-            cpu_read_predators_pos_buf,
-            cpu_read_predators_pursuits_buf,
-            cpu_read_predators_captures_buf,
-        },
-        uniforms: Uniforms {
-            time_uniform,
-            resolution_uniform,
-            uniform_bind_group,
+        layouts,
+        vertex_buf,
+        sim,
+        comparison: Comparison {
+            editing: ComparisonSide::A,
+            split: None,
         },
+        uniforms,
         variables: Variables {
             view_params,
             view_params_storage,
-            boid_params,
-            predator_params,
-            predator_params_storage,
-            boid_params_storage,
+            glyph_params,
+            glyph_params_uniform,
+            density_params,
+            density_params_uniform,
+            color_map,
+            color_params,
+            color_params_uniform,
+            overlay_params,
+            overlay_params_uniform,
+            grid_params,
+            grid_params_uniform,
+            seed,
+            init_params,
             variable_bind_group,
         },
-        controls: Controls { kcm },
+        controls: Controls {
+            kcm,
+            formation_csv: String::from("formations/boids.csv"),
+            drag: None,
+            last_click: None,
+            click_start: None,
+        },
+        trails: Trails {
+            params: trail_params,
+            params_uniform: trail_params_uniform,
+            targets: trail_targets,
+        },
+        post: PostProcessing {
+            params: post_params,
+            params_uniform: post_params_uniform,
+            sampler: post_sampler,
+            targets: post_targets,
+        },
+        hot_reload: HotReload {
+            enabled: cfg!(debug_assertions),
+            last_poll: Instant::now(),
+            modified: HashMap::new(),
+            sources: initial_shader_sources(),
+            errors: BTreeMap::new(),
+        },
+        frame_sequence: FrameSequence {
+            size: [3840, 2160],
+            first_step: 0,
+            last_step: 599,
+            output_dir: String::from("sequences"),
+            run: None,
+        },
+        recorder: Recorder {
+            format: VideoFormat::Y4m,
+            source: RecordSource::Window,
+            size: [1920, 1080],
+            decimation: 1,
+            max_seconds: 0.0,
+            output_dir: String::from("recordings"),
+            recording: None,
+            finishing: Vec::new(),
+        },
+        svg_export: SvgExport {
+            pursuit_lines: true,
+            boundary: true,
+            output_dir: String::from("svg"),
+        },
+        follow: FollowCamera {
+            mode: FollowMode::Off,
+            predator: 0,
+            boid: 0,
+            lag: 0.3,
+            auto_zoom: false,
+            margin: 10.0,
+        },
+        agents: AgentSnapshot::default(),
         main_wid,
         ui,
     }
@@ -385,33 +697,181 @@ fn view(app: &App, model: &Model, frame: Frame) {
     let mut encoder = frame.command_encoder();
     let time = app.time as f32;
     let time_bytes = time.to_ne_bytes();
-    let window = app.main_window();
+    let window = app.window(model.main_wid).unwrap();
     let queue = window.queue();
 
-    if window.inner_size_points().0 != model.uniforms.resolution_uniform.size() as f32 {
-        // Update the resolution uniform buffer with the new window size
-        queue.write_buffer(
-            &model.uniforms.resolution_uniform,
-            0,
-            bytemuck::cast_slice(&[window.inner_size_points().0, window.inner_size_points().1]),
-        );
+    queue.write_buffer(&model.uniforms.time_uniform, 0, &time_bytes);
+
+    match &model.comparison.split {
+        Some(split) => {
+            for pane in comparison_panes(model, split) {
+                encode_scene(&mut encoder, model, &pane, frame.texture_view(), None);
+            }
+        }
+        None => encode_scene(
+            &mut encoder,
+            model,
+            &ScenePane {
+                sim: &model.sim,
+                uniform_bind_group: &model.uniforms.uniform_bind_group,
+                trails: &model.trails.targets,
+                post: &model.post.targets,
+                scissor: None,
+            },
+            frame.texture_view(),
+            None,
+        ),
     }
 
-    queue.write_buffer(&model.uniforms.time_uniform, 0, &time_bytes);
+    // The scale bar draws through the frame's encoder as well
+    drop(encoder);
+    scale_bar::draw_scale_bar(app, model, &frame);
+}
+
+// The trail and main passes into the pane's HDR scene, then the post passes into color_view.
+// Shared by the window and the offscreen targets
+fn encode_scene(
+    encoder: &mut wgpu::CommandEncoder,
+    model: &Model,
+    pane: &ScenePane,
+    color_view: &wgpu::TextureView,
+    resolve_target: Option<&wgpu::TextureView>,
+) {
+    let vertex_range = 0..VERTICES.len() as u32;
+    let instance_range = 0..1;
+    let trail_targets = pane.trails;
+
+    if model.trails.params.enabled != 0 {
+        let mut trail_pass = wgpu::RenderPassBuilder::new()
+            .color_attachment(&trail_targets.cur_view, |color| color)
+            .begin(encoder);
+
+        if let Some([x, y, w, h]) = pane.scissor {
+            trail_pass.set_scissor_rect(x, y, w, h);
+        }
+        trail_pass.set_bind_group(0, &pane.sim.compute_bind_group, &[]);
+        trail_pass.set_bind_group(1, pane.uniform_bind_group, &[]);
+        trail_pass.set_bind_group(2, &model.variables.variable_bind_group, &[]);
+        trail_pass.set_bind_group(3, &trail_targets.prev_bind_group, &[]);
+        trail_pass.set_pipeline(&model.pipelines.trail_pipeline);
+        trail_pass.set_vertex_buffer(0, model.vertex_buf.slice(..));
+        trail_pass.draw(vertex_range.clone(), instance_range.clone());
+    }
+
+    {
+        let scene_msaa_view = pane
+            .post
+            .scene_msaa_texture
+            .as_ref()
+            .map(|texture| texture.view().build());
+        let (scene_view, scene_resolve) = match &scene_msaa_view {
+            Some(msaa_view) => (msaa_view, Some(&pane.post.scene_view)),
+            None => (&pane.post.scene_view, None),
+        };
+
+        let mut render_pass = wgpu::RenderPassBuilder::new()
+            .color_attachment(scene_view, |color| color.resolve_target(scene_resolve))
+            .begin(encoder);
+
+        if let Some([x, y, w, h]) = pane.scissor {
+            render_pass.set_scissor_rect(x, y, w, h);
+        }
+        render_pass.set_bind_group(0, &pane.sim.compute_bind_group, &[]);
+        render_pass.set_bind_group(1, pane.uniform_bind_group, &[]);
+        render_pass.set_bind_group(2, &model.variables.variable_bind_group, &[]);
+        render_pass.set_bind_group(3, &trail_targets.cur_bind_group, &[]);
+        render_pass.set_pipeline(&model.pipelines.render_pipeline);
+        render_pass.set_vertex_buffer(0, model.vertex_buf.slice(..));
+        render_pass.draw(vertex_range, instance_range);
+    }
 
-    let mut render_pass = wgpu::RenderPassBuilder::new()
-        .color_attachment(frame.texture_view(), |color| color)
-        .begin(&mut encoder);
+    if model.trails.params.enabled != 0 {
+        encoder.copy_texture_to_texture(
+            trail_targets.cur_texture.as_image_copy(),
+            trail_targets.prev_texture.as_image_copy(),
+            trail_targets.cur_texture.extent(),
+        );
+    }
 
-    render_pass.set_bind_group(0, &model.compute_bind_group, &[]);
-    render_pass.set_bind_group(1, &model.uniforms.uniform_bind_group, &[]);
-    render_pass.set_bind_group(2, &model.variables.variable_bind_group, &[]);
-    render_pass.set_pipeline(&model.pipelines.render_pipeline);
-    render_pass.set_vertex_buffer(0, model.buffers.vertex_buf.slice(..));
+    encode_post(encoder, model, pane, color_view, resolve_target);
+}
 
+// Bloom runs at half resolution: bright pass into bloom[0], blurred across into bloom[1] and
+// back, then the composite pass combines it with the scene
+fn encode_post(
+    encoder: &mut wgpu::CommandEncoder,
+    model: &Model,
+    pane: &ScenePane,
+    color_view: &wgpu::TextureView,
+    resolve_target: Option<&wgpu::TextureView>,
+) {
+    let targets = pane.post;
     let vertex_range = 0..VERTICES.len() as u32;
     let instance_range = 0..1;
-    render_pass.draw(vertex_range, instance_range);
+    let pipelines = &model.pipelines;
+
+    if model.post.params.bloom != 0 {
+        let bloom_passes = [
+            (
+                &pipelines.post_bright_pipeline,
+                &targets.bright_bind_group,
+                &targets.bloom_views[0],
+            ),
+            (
+                &pipelines.post_blur_h_pipeline,
+                &targets.blur_h_bind_group,
+                &targets.bloom_views[1],
+            ),
+            (
+                &pipelines.post_blur_v_pipeline,
+                &targets.blur_v_bind_group,
+                &targets.bloom_views[0],
+            ),
+        ];
+
+        for (pipeline, bind_group, view) in bloom_passes {
+            let mut bloom_pass = wgpu::RenderPassBuilder::new()
+                .color_attachment(view, |color| color)
+                .begin(encoder);
+
+            bloom_pass.set_bind_group(0, bind_group, &[]);
+            bloom_pass.set_pipeline(pipeline);
+            bloom_pass.set_vertex_buffer(0, model.vertex_buf.slice(..));
+            bloom_pass.draw(vertex_range.clone(), instance_range.clone());
+        }
+    }
+
+    // A clear would wipe the pane drawn before this one
+    let load_op = match pane.scissor {
+        Some(_) => wgpu::LoadOp::Load,
+        None => wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+    };
+
+    let mut composite_pass = wgpu::RenderPassBuilder::new()
+        .color_attachment(color_view, |color| {
+            color.resolve_target(resolve_target).load_op(load_op)
+        })
+        .begin(encoder);
+
+    if let Some([x, y, w, h]) = pane.scissor {
+        composite_pass.set_scissor_rect(x, y, w, h);
+    }
+    composite_pass.set_bind_group(0, &targets.composite_bind_group, &[]);
+    composite_pass.set_pipeline(&pipelines.post_composite_pipeline);
+    composite_pass.set_vertex_buffer(0, model.vertex_buf.slice(..));
+    composite_pass.draw(vertex_range, instance_range);
+}
+
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    if key == Key::F11 {
+        let window = app.window(model.main_wid).unwrap();
+        window.set_fullscreen(!window.is_fullscreen());
+    }
+
+    // Handled here rather than in debug_controls, which sees held keys every frame
+    if key == Key::R && matches!(model.controls.kcm, KeyboardControlMode::Debug) {
+        toggle_recording(app, model);
+    }
 }
 
 fn raw_ui_event(_app: &App, model: &mut Model, event: &nannou::winit::event::WindowEvent) {
@@ -455,58 +915,110 @@ fn get_bind_group_layouts(device: &Device) -> Layouts {
         });
 
     let variable_bind_group_layout =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: false },
-                    has_dynamic_offset: false,
-                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<ViewParams>() as _),
-                },
-                count: None,
-            }],
-            label: Some("variable_bind_group_layout"),
-        });
-
-    let compute_bind_group_layout =
         device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Storage { read_only: false },
                         has_dynamic_offset: false,
-                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<Boid>() as _),
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<ViewParams>() as _
+                        ),
                     },
                     count: None,
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
                         min_binding_size: wgpu::BufferSize::new(
-                            std::mem::size_of::<BoidParams>() as _
+                            std::mem::size_of::<GlyphParams>() as _
                         ),
                     },
                     count: None,
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
-                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<Boid>() as _),
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<ColorParams>() as _
+                        ),
                     },
                     count: None,
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<OverlayParams>() as _,
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<GridParams>() as _
+                        ),
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("variable_bind_group_layout"),
+        });
+
+    let compute_bind_group_layout =
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<Boid>() as _),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<BoidParams>() as _
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<Boid>() as _),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Storage { read_only: false },
                         has_dynamic_offset: false,
@@ -530,7 +1042,7 @@ fn get_bind_group_layouts(device: &Device) -> Layouts {
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 5,
-                    visibility: wgpu::ShaderStages::COMPUTE,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Storage { read_only: false },
                         has_dynamic_offset: false,
@@ -564,20 +1076,238 @@ fn get_bind_group_layouts(device: &Device) -> Layouts {
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 8,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<&[f32; NUM_BOIDS]>() as _,
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 9,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<FoodPatch>() as _
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 10,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<ForageState>() as _
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 11,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<FoodParams>() as _
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 12,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<LeaderControl>() as _,
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 13,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<WanderState>() as _
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 14,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<SeedUniform>() as _
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 15,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<InitParams>() as _
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 16,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<&[u32; NUM_BOIDS]>() as _,
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 17,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<DensityParams>() as _,
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 18,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<
+                            [[f32; 2]; DENSITY_GRID_X * DENSITY_GRID_Y],
+                        >() as _),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 19,
+                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<StrategyTargets>() as _,
+                        ),
+                    },
+                    count: None,
+                },
             ],
             label: Some("compute_bind_group_layout"),
         });
 
+    let trail_bind_group_layout =
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<TrailParams>() as _
+                        ),
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("trail_bind_group_layout"),
+        });
+
+    let post_texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    };
+
+    let post_bind_group_layout =
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                post_texture_entry(0),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                post_texture_entry(2),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<PostParams>() as _
+                        ),
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("post_bind_group_layout"),
+        });
+
     let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Unified Pipeline Layout"),
         bind_group_layouts: &[
             &compute_bind_group_layout,
             &uniform_bind_group_layout,
             &variable_bind_group_layout,
+            &trail_bind_group_layout,
         ],
         push_constant_ranges: &[],
     });
 
+    let post_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Post Processing Pipeline Layout"),
+        bind_group_layouts: &[&post_bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
     let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Compute Pipeline Layout"),
         bind_group_layouts: &[&compute_bind_group_layout],
@@ -588,64 +1318,512 @@ fn get_bind_group_layouts(device: &Device) -> Layouts {
         uniform_bind_group_layout,
         variable_bind_group_layout,
         compute_bind_group_layout,
+        trail_bind_group_layout,
+        post_bind_group_layout,
         render_pipeline_layout,
+        post_pipeline_layout,
         compute_pipeline_layout,
     }
 }
 
-fn get_storage_buffers(
+fn create_render_pipeline(
     device: &Device,
-    view_params: ViewParams,
-    boid_params: BoidParams,
-    predator_params: PredatorParams,
-) -> (wgpu::Buffer, wgpu::Buffer, wgpu::Buffer) {
-    let view_params_storage = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Parameters Storage Buffer"),
-        contents: bytemuck::cast_slice(&[
-            view_params.x_shift,
-            view_params.y_shift,
-            view_params.zoom,
-            view_params.time_modifier,
-        ]),
+    layouts: &Layouts,
+    vs_mod: &wgpu::ShaderModule,
+    fs_mod: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    wgpu::RenderPipelineBuilder::from_layout(&layouts.render_pipeline_layout, vs_mod)
+        .fragment_shader(fs_mod)
+        .color_format(format)
+        .add_vertex_buffer::<Vertex>(&wgpu::vertex_attr_array![0 => Float32x2])
+        .sample_count(sample_count)
+        .build(device)
+}
+
+// Vertex and fragment stages both come from post.wgsl, one pipeline per fragment entry point
+fn create_post_pipeline(
+    device: &Device,
+    layouts: &Layouts,
+    module: &wgpu::ShaderModule,
+    entry_point: &str,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    wgpu::RenderPipelineBuilder::from_layout(&layouts.post_pipeline_layout, module)
+        .vertex_entry_point("fullscreen")
+        .fragment_shader(module)
+        .fragment_entry_point(entry_point)
+        .color_format(format)
+        .add_vertex_buffer::<Vertex>(&wgpu::vertex_attr_array![0 => Float32x2])
+        .sample_count(sample_count)
+        .build(device)
+}
+
+// The scene renders into scene_view instead of the frame, the bright pass and both blurs run
+// on the half resolution bloom textures, and the composite pass reads the scene and bloom[0]
+pub(crate) fn create_post_targets(
+    device: &Device,
+    layouts: &Layouts,
+    post_params_uniform: &wgpu::Buffer,
+    sampler: &wgpu::Sampler,
+    size: [u32; 2],
+    sample_count: u32,
+) -> PostTargets {
+    // Same format as the frame so the render pipeline can draw into either
+    let scene_texture = wgpu::TextureBuilder::new()
+        .size(size)
+        .format(Frame::TEXTURE_FORMAT)
+        .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+        .build(device);
+    let scene_view = scene_texture.view().build();
+
+    let scene_msaa_texture = (sample_count > 1).then(|| {
+        wgpu::TextureBuilder::new()
+            .size(size)
+            .format(Frame::TEXTURE_FORMAT)
+            .sample_count(sample_count)
+            .usage(wgpu::TextureUsages::RENDER_ATTACHMENT)
+            .build(device)
+    });
+
+    let bloom_size = size.map(|v| (v / 2).max(1));
+    let bloom_textures = [(); 2].map(|_| {
+        wgpu::TextureBuilder::new()
+            .size(bloom_size)
+            .format(POST_FORMAT)
+            .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+            .build(device)
+    });
+    let bloom_views = [0, 1].map(|i| bloom_textures[i].view().build());
+
+    // A pass can't sample the texture it renders into, so passes that don't read the bloom
+    // texture bind their source there as well
+    let create_bind_group = |src: &wgpu::TextureView, bloom: &wgpu::TextureView, label| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layouts.post_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(src),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(bloom),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: post_params_uniform.as_entire_binding(),
+                },
+            ],
+            label: Some(label),
+        })
+    };
+
+    let bright_bind_group = create_bind_group(&scene_view, &scene_view, "post_bright_bind_group");
+    let blur_h_bind_group =
+        create_bind_group(&bloom_views[0], &bloom_views[0], "post_blur_h_bind_group");
+    let blur_v_bind_group =
+        create_bind_group(&bloom_views[1], &bloom_views[1], "post_blur_v_bind_group");
+    let composite_bind_group =
+        create_bind_group(&scene_view, &bloom_views[0], "post_composite_bind_group");
+
+    PostTargets {
+        scene_msaa_texture,
+        scene_view,
+        bloom_views,
+        bright_bind_group,
+        blur_h_bind_group,
+        blur_v_bind_group,
+        composite_bind_group,
+    }
+}
+
+// Both trail textures plus a bind group reading each, the trail pass reads prev and renders into
+// cur, the main pass reads cur, then cur is copied back over prev for the next frame
+pub(crate) fn create_trail_targets(
+    device: &Device,
+    layouts: &Layouts,
+    trail_params_uniform: &wgpu::Buffer,
+    size: [u32; 2],
+) -> TrailTargets {
+    let create_texture = || {
+        wgpu::TextureBuilder::new()
+            .size(size)
+            .format(TRAIL_FORMAT)
+            .usage(
+                wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::COPY_DST,
+            )
+            .build(device)
+    };
+
+    let prev_texture = create_texture();
+    let cur_texture = create_texture();
+    let prev_view = prev_texture.view().build();
+    let cur_view = cur_texture.view().build();
+
+    let create_bind_group = |view: &wgpu::TextureView, label| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layouts.trail_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: trail_params_uniform.as_entire_binding(),
+                },
+            ],
+            label: Some(label),
+        })
+    };
+
+    let prev_bind_group = create_bind_group(&prev_view, "trail_prev_bind_group");
+    let cur_bind_group = create_bind_group(&cur_view, "trail_cur_bind_group");
+
+    TrailTargets {
+        prev_texture,
+        cur_texture,
+        cur_view,
+        prev_bind_group,
+        cur_bind_group,
+    }
+}
+
+fn create_compute_pipeline(
+    device: &Device,
+    layouts: &Layouts,
+    label: &str,
+    module: &wgpu::ShaderModule,
+    entry_point: &str,
+) -> wgpu::ComputePipeline {
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(label),
+        layout: Some(&layouts.compute_pipeline_layout),
+        module,
+        entry_point,
+    })
+}
+
+// Buffers and compute bind group for one simulation, sharing the time, seed, init and density
+// uniforms. Agents are placed by the init pipelines, in model() or reset_simulation.
+pub(crate) fn create_simulation(
+    device: &Device,
+    layouts: &Layouts,
+    uniforms: &Uniforms,
+    density_params_uniform: &wgpu::Buffer,
+    params: SimulationParams,
+) -> Simulation {
+    let (boid_params_storage, predator_params_storage, food_params_storage, leader_storage) =
+        get_storage_buffers(device, &params);
+
+    let boids_pos_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Boids Positions Buffer"),
+        size: (std::mem::size_of::<[Boid; NUM_BOIDS]>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let predator_pos_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Predator Positions Buffer"),
+        size: (std::mem::size_of::<[Boid; NUM_PREDATORS]>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let pursuits_buf = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Pursuits IDs Buffer"),
+        contents: unsafe { wgpu::bytes::from_slice(&[0xFFFFFFFFu32; NUM_PREDATORS]) },
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let captures_buf = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Predator Capture Count Buffer"),
+        contents: unsafe { wgpu::bytes::from_slice(&[0u32; NUM_PREDATORS]) },
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let captured_boids_buf = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Captured Boid IDs Buffer"),
+        contents: unsafe { wgpu::bytes::from_slice(&[1.0f32; NUM_BOIDS]) },
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+    });
+
+    // Current levels followed by the next step's, see spread_alarm
+    let alarm_buf = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Boid Alarm Levels Buffer"),
+        contents: unsafe { wgpu::bytes::from_slice(&[0.0f32; 2 * NUM_BOIDS]) },
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let food_patches = initial_food_patches(params.food_params.max_food);
+
+    let food_buf = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Food Patches Buffer"),
+        contents: bytemuck::cast_slice(&food_patches),
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+    });
+
+    // Every boid starts fed with no push towards food
+    let forage_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Boid Forage State Buffer"),
+        size: (std::mem::size_of::<[ForageState; NUM_BOIDS]>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    // Written by the SAC pass, read back for colouring and the inspector
+    let neighbours_buf = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Boid Neighbour Count Buffer"),
+        contents: unsafe { wgpu::bytes::from_slice(&[0u32; NUM_BOIDS]) },
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+    });
+
+    // Instant density and its running average per grid cell, both zero until the first splat
+    let density_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Density Grid Buffer"),
+        size: (std::mem::size_of::<[[f32; 2]; DENSITY_GRID_X * DENSITY_GRID_Y]>())
+            as wgpu::BufferAddress,
         usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
     });
 
+    // Written by the predator pursuit pass for the debug overlays
+    let strategy_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Predator Strategy Targets Buffer"),
+        size: (std::mem::size_of::<StrategyTargets>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    // Seeded by compute_boid_positions, then carried from step to step by the SAC pass
+    let wander_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Boid Wander State Buffer"),
+        size: (std::mem::size_of::<[WanderState; NUM_BOIDS]>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let cpu_read_boids_pos_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("CPU Readable Buffer - Boids"),
+        size: (std::mem::size_of::<[Boid; NUM_BOIDS]>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let cpu_read_predators_pos_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("CPU Readable Buffer - Predators"),
+        size: (std::mem::size_of::<[Boid; NUM_PREDATORS]>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let cpu_read_predators_pursuits_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("CPU Readable Buffer - Predators Current Target Boid IDs"),
+        size: (std::mem::size_of::<[u32; NUM_PREDATORS]>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let cpu_read_predators_captures_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("CPU Readable Buffer - Predators Current Target Boid IDs"),
+        size: (std::mem::size_of::<[u32; NUM_PREDATORS]>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let cpu_read_captured_boids_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("CPU Readable Buffer - Boids Captured State"),
+        size: (std::mem::size_of::<[f32; NUM_BOIDS]>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let cpu_read_neighbours_buf = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("CPU Readable Buffer - Boid Neighbour Counts"),
+        size: (std::mem::size_of::<[u32; NUM_BOIDS]>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &layouts.compute_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: boids_pos_buf.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: boid_params_storage.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: predator_pos_buf.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: predator_params_storage.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: uniforms.time_uniform.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: pursuits_buf.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: captures_buf.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: captured_boids_buf.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 8,
+                resource: alarm_buf.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 9,
+                resource: food_buf.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 10,
+                resource: forage_buf.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 11,
+                resource: food_params_storage.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 12,
+                resource: leader_storage.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 13,
+                resource: wander_buf.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 14,
+                resource: uniforms.seed_uniform.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 15,
+                resource: uniforms.init_params_uniform.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 16,
+                resource: neighbours_buf.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 17,
+                resource: density_params_uniform.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 18,
+                resource: density_buf.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 19,
+                resource: strategy_buf.as_entire_binding(),
+            },
+        ],
+        label: Some("compute_bind_group"),
+    });
+
+    Simulation {
+        params,
+        boid_params_storage,
+        predator_params_storage,
+        food_params_storage,
+        leader_storage,
+        buffers: Buffers {
+            boids_pos_buf,
+            cpu_read_boids_pos_buf,
+            predator_pos_buf,
+            pursuits_buf,
+            captures_buf,
+            captured_boids_buf,
+            alarm_buf,
+            food_buf,
+            forage_buf,
+            wander_buf,
+            neighbours_buf,
+            density_buf,
+            strategy_buf,
+            cpu_read_predators_pos_buf,
+            cpu_read_predators_pursuits_buf,
+            cpu_read_predators_captures_buf,
+            cpu_read_captured_boids_buf,
+            cpu_read_neighbours_buf,
+        },
+        compute_bind_group,
+    }
+}
+
+fn get_storage_buffers(
+    device: &Device,
+    params: &SimulationParams,
+) -> (wgpu::Buffer, wgpu::Buffer, wgpu::Buffer, wgpu::Buffer) {
     let boid_params_storage = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Boid Parameters Storage Buffer"),
-        contents: bytemuck::cast_slice(&[
-            boid_params.max_velocity,
-            boid_params.min_velocity,
-            boid_params.turn_factor,
-            boid_params.visual_range,
-            boid_params.protected_range,
-            boid_params.centering_factor,
-            boid_params.self_avoid_factor,
-            boid_params.predator_avoid_factor,
-            boid_params.matching_factor,
-        ]),
+        contents: bytemuck::cast_slice(&[params.boid_params]),
         usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
     });
 
     let predator_params_storage = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Boid Parameters Storage Buffer"),
-        contents: bytemuck::cast_slice(&[
-            predator_params.max_velocity,
-            predator_params.min_velocity,
-            predator_params.turn_factor,
-            predator_params.pursuit_factor,
-            predator_params.pursuit_multiplier,
-            predator_params.matching_factor,
-            predator_params.self_avoid_factor,
-            predator_params.visual_range,
-            predator_params.protected_range,
-            predator_params.interest_range,
-        ]),
+        label: Some("Predator Parameters Storage Buffer"),
+        contents: bytemuck::cast_slice(&[params.predator_params]),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let food_params_storage = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Food Parameters Storage Buffer"),
+        contents: bytemuck::cast_slice(&[params.food_params]),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let leader_storage = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Leader Control Storage Buffer"),
+        contents: bytemuck::cast_slice(&[params.leader]),
         usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
     });
 
     (
-        view_params_storage,
         boid_params_storage,
         predator_params_storage,
+        food_params_storage,
+        leader_storage,
     )
 }
@@ -1,3 +1,4 @@
 pub(crate) mod structs;
+pub(crate) mod wgsl;
 
 pub(crate) use structs::*;
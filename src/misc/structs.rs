@@ -1,109 +1,600 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    path::PathBuf,
+    sync::{
+        atomic::AtomicU32,
+        mpsc::{Receiver, Sender, SyncSender},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Instant, SystemTime},
+};
+
 use nannou::{
     glam::Vec2,
+    image::RgbaImage,
     prelude::WindowId,
     wgpu::{self, BindGroupLayout, PipelineLayout},
 };
 use nannou_egui::Egui;
 
+use super::wgsl::wgsl_struct;
+
 pub(crate) struct Model {
     pub(crate) pipelines: Pipelines,
-    pub(crate) compute_bind_group: wgpu::BindGroup,
-    pub(crate) buffers: Buffers,
+    pub(crate) layouts: Layouts,
+    pub(crate) vertex_buf: wgpu::Buffer,
+    // The simulation the controls act on, the one on the left unless comparing and editing B
+    pub(crate) sim: Simulation,
+    pub(crate) comparison: Comparison,
     pub(crate) uniforms: Uniforms,
     pub(crate) variables: Variables,
     pub(crate) controls: Controls,
+    pub(crate) trails: Trails,
+    pub(crate) post: PostProcessing,
+    pub(crate) hot_reload: HotReload,
+    pub(crate) frame_sequence: FrameSequence,
+    pub(crate) recorder: Recorder,
+    pub(crate) svg_export: SvgExport,
+    pub(crate) follow: FollowCamera,
+    pub(crate) agents: AgentSnapshot,
     pub(crate) main_wid: WindowId,
     pub(crate) ui: Egui,
 }
 
 pub(crate) struct Pipelines {
     pub(crate) render_pipeline: wgpu::RenderPipeline,
+    pub(crate) trail_pipeline: wgpu::RenderPipeline,
+    pub(crate) post_bright_pipeline: wgpu::RenderPipeline,
+    pub(crate) post_blur_h_pipeline: wgpu::RenderPipeline,
+    pub(crate) post_blur_v_pipeline: wgpu::RenderPipeline,
+    pub(crate) post_composite_pipeline: wgpu::RenderPipeline,
     pub(crate) compute_boid_pos_pipeline: wgpu::ComputePipeline,
     pub(crate) compute_predator_pos_pipeline: wgpu::ComputePipeline,
+    pub(crate) compute_alarm_pipeline: wgpu::ComputePipeline,
     pub(crate) compute_sac_pipeline: wgpu::ComputePipeline,
     pub(crate) compute_pursuit_curve_pipeline: wgpu::ComputePipeline,
+    pub(crate) compute_forage_pipeline: wgpu::ComputePipeline,
+    pub(crate) compute_food_pipeline: wgpu::ComputePipeline,
+    pub(crate) compute_density_pipeline: wgpu::ComputePipeline,
 }
 
 pub(crate) struct Uniforms {
     pub(crate) time_uniform: wgpu::Buffer,
+    pub(crate) resolution: ResolutionUniform,
     pub(crate) resolution_uniform: wgpu::Buffer,
+    pub(crate) seed_uniform: wgpu::Buffer,
+    pub(crate) init_params_uniform: wgpu::Buffer,
     pub(crate) uniform_bind_group: wgpu::BindGroup,
 }
 
+// Everything one simulation owns. The pipelines, camera and render settings are shared, so a
+// second one for the A/B comparison only needs another set of these
+pub(crate) struct Simulation {
+    pub(crate) params: SimulationParams,
+    pub(crate) boid_params_storage: wgpu::Buffer,
+    pub(crate) predator_params_storage: wgpu::Buffer,
+    pub(crate) food_params_storage: wgpu::Buffer,
+    pub(crate) leader_storage: wgpu::Buffer,
+    pub(crate) buffers: Buffers,
+    pub(crate) compute_bind_group: wgpu::BindGroup,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SimulationParams {
+    pub(crate) boid_params: BoidParams,
+    pub(crate) predator_params: PredatorParams,
+    pub(crate) food_params: FoodParams,
+    pub(crate) leader: LeaderControl,
+}
+
+pub(crate) struct Comparison {
+    // Which simulation Model.sim holds, the other one is in split
+    pub(crate) editing: ComparisonSide,
+    pub(crate) split: Option<SplitView>,
+}
+
+// The second simulation and what the window needs to draw both side by side
+pub(crate) struct SplitView {
+    pub(crate) other: Simulation,
+    // Window sized like Model.trails and Model.post, swapped along with the simulation
+    pub(crate) trails: TrailTargets,
+    pub(crate) post: PostTargets,
+    // Time plus the size and origin of the left and right panes
+    pub(crate) pane_bind_groups: [wgpu::BindGroup; 2],
+}
+
+// One simulation drawn into a render target by encode_scene
+pub(crate) struct ScenePane<'a> {
+    pub(crate) sim: &'a Simulation,
+    pub(crate) uniform_bind_group: &'a wgpu::BindGroup,
+    pub(crate) trails: &'a TrailTargets,
+    pub(crate) post: &'a PostTargets,
+    // x, y, width and height in pixels, None draws over the whole target
+    pub(crate) scissor: Option<[u32; 4]>,
+}
+
 pub(crate) struct Buffers {
-    pub(crate) vertex_buf: wgpu::Buffer,
     pub(crate) boids_pos_buf: wgpu::Buffer,
     pub(crate) cpu_read_boids_pos_buf: wgpu::Buffer,
     pub(crate) predator_pos_buf: wgpu::Buffer,
     pub(crate) pursuits_buf: wgpu::Buffer,
     pub(crate) captures_buf: wgpu::Buffer,
     pub(crate) captured_boids_buf: wgpu::Buffer,
+    pub(crate) alarm_buf: wgpu::Buffer,
+    pub(crate) food_buf: wgpu::Buffer,
+    pub(crate) forage_buf: wgpu::Buffer,
+    pub(crate) wander_buf: wgpu::Buffer,
+    pub(crate) neighbours_buf: wgpu::Buffer,
+    pub(crate) density_buf: wgpu::Buffer,
+    pub(crate) strategy_buf: wgpu::Buffer,
     pub(crate) cpu_read_predators_pos_buf: wgpu::Buffer,
     pub(crate) cpu_read_predators_pursuits_buf: wgpu::Buffer,
     pub(crate) cpu_read_predators_captures_buf: wgpu::Buffer,
+    pub(crate) cpu_read_captured_boids_buf: wgpu::Buffer,
+    pub(crate) cpu_read_neighbours_buf: wgpu::Buffer,
+}
+
+// Host copies of Model.sim's cpu_read buffers, refreshed once a frame by update_cpu_read_buffers
+#[derive(Default)]
+pub(crate) struct AgentSnapshot {
+    pub(crate) boids: Vec<Boid>,
+    pub(crate) predators: Vec<Boid>,
+    pub(crate) pursuits: Vec<u32>,
+    pub(crate) captures: Vec<u32>,
+    pub(crate) captured: Vec<f32>,
+    pub(crate) neighbours: Vec<u32>,
 }
 
 pub(crate) struct Variables {
     pub(crate) view_params: ViewParams,
     pub(crate) view_params_storage: wgpu::Buffer,
-    pub(crate) boid_params: BoidParams,
-    pub(crate) boid_params_storage: wgpu::Buffer,
-    pub(crate) predator_params: PredatorParams,
-    pub(crate) predator_params_storage: wgpu::Buffer,
+    pub(crate) glyph_params: GlyphParams,
+    pub(crate) glyph_params_uniform: wgpu::Buffer,
+    pub(crate) density_params: DensityParams,
+    pub(crate) density_params_uniform: wgpu::Buffer,
+    pub(crate) color_map: ColorMap,
+    pub(crate) color_params: ColorParams,
+    pub(crate) color_params_uniform: wgpu::Buffer,
+    pub(crate) overlay_params: OverlayParams,
+    pub(crate) overlay_params_uniform: wgpu::Buffer,
+    pub(crate) grid_params: GridParams,
+    pub(crate) grid_params_uniform: wgpu::Buffer,
+    pub(crate) seed: SeedUniform,
+    pub(crate) init_params: InitParams,
     pub(crate) variable_bind_group: wgpu::BindGroup,
 }
 
 #[derive(Debug)]
 pub(crate) struct Controls {
     pub(crate) kcm: KeyboardControlMode,
+    pub(crate) formation_csv: String,
+    // Last cursor position in points while a drag pans the view
+    pub(crate) drag: Option<Vec2>,
+    // app.time of the last left click, to tell double clicks apart
+    pub(crate) last_click: Option<f32>,
+    // Where the left button went down, releasing it close by picks an agent
+    pub(crate) click_start: Option<Vec2>,
+}
+
+pub(crate) struct Trails {
+    pub(crate) params: TrailParams,
+    pub(crate) params_uniform: wgpu::Buffer,
+    pub(crate) targets: TrailTargets,
+}
+
+// Per pixel trail state, every render target has its own set at its own size
+pub(crate) struct TrailTargets {
+    pub(crate) prev_texture: wgpu::Texture,
+    pub(crate) cur_texture: wgpu::Texture,
+    pub(crate) cur_view: wgpu::TextureView,
+    // Group 3 of the trail pass, reads last frame's trails
+    pub(crate) prev_bind_group: wgpu::BindGroup,
+    // Group 3 of the main render pass, reads this frame's trails
+    pub(crate) cur_bind_group: wgpu::BindGroup,
+}
+
+pub(crate) struct PostProcessing {
+    pub(crate) params: PostParams,
+    pub(crate) params_uniform: wgpu::Buffer,
+    pub(crate) sampler: wgpu::Sampler,
+    pub(crate) targets: PostTargets,
+}
+
+// The HDR scene and bloom buffers of one render target, sized with it
+pub(crate) struct PostTargets {
+    // Only used when the render pipeline is multisampled, resolves into scene_view
+    pub(crate) scene_msaa_texture: Option<wgpu::Texture>,
+    pub(crate) scene_view: wgpu::TextureView,
+    // Half resolution, the blur ping-pongs between the two and ends up back in the first
+    pub(crate) bloom_views: [wgpu::TextureView; 2],
+    pub(crate) bright_bind_group: wgpu::BindGroup,
+    pub(crate) blur_h_bind_group: wgpu::BindGroup,
+    pub(crate) blur_v_bind_group: wgpu::BindGroup,
+    pub(crate) composite_bind_group: wgpu::BindGroup,
+}
+
+pub(crate) struct HotReload {
+    pub(crate) enabled: bool,
+    pub(crate) last_poll: Instant,
+    // Modification times of every file under src/shaders at the last poll
+    pub(crate) modified: HashMap<PathBuf, SystemTime>,
+    // Composed source of each pipeline shader as it was last built successfully
+    pub(crate) sources: HashMap<&'static str, String>,
+    pub(crate) errors: BTreeMap<&'static str, String>,
+}
+
+// Renders the scene into a texture of any size instead of the window's frame
+pub(crate) struct OffscreenTarget {
+    pub(crate) size: [u32; 2],
+    // Own time and resolution uniforms so the window keeps rendering as it was
+    pub(crate) time_uniform: wgpu::Buffer,
+    pub(crate) uniform_bind_group: wgpu::BindGroup,
+    // Only used when the render pipeline is multisampled, resolves into texture
+    pub(crate) msaa_texture: Option<wgpu::Texture>,
+    pub(crate) texture: wgpu::Texture,
+    pub(crate) view: wgpu::TextureView,
+    pub(crate) trails: TrailTargets,
+    pub(crate) post: PostTargets,
+}
+
+pub(crate) struct FrameSequence {
+    pub(crate) size: [u32; 2],
+    // Steps are counted from a reset, frames before first_step are simulated but not rendered
+    pub(crate) first_step: u32,
+    pub(crate) last_step: u32,
+    pub(crate) output_dir: String,
+    pub(crate) run: Option<SequenceRun>,
+}
+
+pub(crate) struct SequenceRun {
+    pub(crate) step: u32,
+    pub(crate) dir: PathBuf,
+    pub(crate) target: OffscreenTarget,
+    pub(crate) capturer: wgpu::TextureCapturer,
+    // Frames that failed to save on the capture threads, checked before each step
+    pub(crate) error_sender: Sender<String>,
+    pub(crate) errors: Receiver<String>,
+}
+
+pub(crate) struct SvgExport {
+    pub(crate) pursuit_lines: bool,
+    pub(crate) boundary: bool,
+    pub(crate) output_dir: String,
+}
+
+pub(crate) struct FollowCamera {
+    pub(crate) mode: FollowMode,
+    // Followed by FollowMode::Predator and FollowMode::PredatorTarget
+    pub(crate) predator: usize,
+    pub(crate) boid: usize,
+    // Seconds for the camera to close about two thirds of the distance to its target
+    pub(crate) lag: f32,
+    // Zooms so every live boid stays in view, margin is in world units around them
+    pub(crate) auto_zoom: bool,
+    pub(crate) margin: f32,
+}
+
+pub(crate) struct Recorder {
+    pub(crate) format: VideoFormat,
+    pub(crate) source: RecordSource,
+    // Size of RecordSource::Offscreen, the window source uses the framebuffer size
+    pub(crate) size: [u32; 2],
+    // Keeps every nth simulation frame, the video plays back at 60 / decimation fps
+    pub(crate) decimation: u32,
+    // Length of the video, not of the recording session. 0 records until stopped
+    pub(crate) max_seconds: f32,
+    pub(crate) output_dir: String,
+    pub(crate) recording: Option<Recording>,
+    // Stopped recordings whose writers are still encoding, reported once they're done
+    pub(crate) finishing: Vec<FinishingRecording>,
+}
+
+pub(crate) struct Recording {
+    pub(crate) path: PathBuf,
+    pub(crate) target: OffscreenTarget,
+    pub(crate) capturer: wgpu::TextureCapturer,
+    // Simulation frames seen and video frames kept since the recording started
+    pub(crate) frames_seen: u32,
+    pub(crate) frames_kept: u32,
+    pub(crate) max_frames: Option<u32>,
+    // Captured frames go to a writer thread, tagged with their index as they can arrive out of
+    // order
+    pub(crate) frame_sender: SyncSender<(u32, RgbaImage)>,
+    // Frames thrown away because the writer's queue was full
+    pub(crate) frames_dropped: Arc<AtomicU32>,
+    pub(crate) writer: JoinHandle<io::Result<()>>,
+}
+
+pub(crate) struct FinishingRecording {
+    pub(crate) path: PathBuf,
+    pub(crate) frames_kept: u32,
+    pub(crate) frames_dropped: Arc<AtomicU32>,
+    pub(crate) writer: JoinHandle<io::Result<()>>,
 }
 
 pub(crate) type TimeUniform = f32;
-pub(crate) type ResolutionUniform = Vec2;
+pub(crate) type SeedUniform = u32;
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub(crate) struct ViewParams {
-    pub(crate) x_shift: f32,
-    pub(crate) y_shift: f32,
-    pub(crate) zoom: f32,
-    pub(crate) time_modifier: f32,
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct ResolutionUniform {
+        // Size of the area drawn into in physical pixels, the whole framebuffer unless the view
+        // is split
+        pub(crate) xy: [f32; 2],
+        // Top left of that area, in the same space as @builtin(position)
+        pub(crate) origin: [f32; 2],
+        // Physical pixels per logical point
+        pub(crate) scale_factor: f32,
+        pub(crate) _padding: f32,
+    }
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub(crate) struct Boid {
-    pub(crate) pos: [f32; 2],
-    pub(crate) vel: [f32; 2],
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct ViewParams {
+        pub(crate) x_shift: f32,
+        pub(crate) y_shift: f32,
+        pub(crate) zoom: f32,
+        pub(crate) time_modifier: f32,
+    }
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub(crate) struct BoidParams {
-    pub(crate) max_velocity: f32,
-    pub(crate) min_velocity: f32,
-    pub(crate) turn_factor: f32,
-    pub(crate) visual_range: f32,
-    pub(crate) protected_range: f32,
-    pub(crate) centering_factor: f32,
-    pub(crate) self_avoid_factor: f32,
-    pub(crate) predator_avoid_factor: f32,
-    pub(crate) matching_factor: f32,
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct GlyphParams {
+        pub(crate) boid_size: f32,
+        pub(crate) predator_size: f32,
+        pub(crate) boid_shape: u32,
+        pub(crate) predator_shape: u32,
+        pub(crate) units: u32,
+    }
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub(crate) struct PredatorParams {
-    pub(crate) max_velocity: f32,
-    pub(crate) min_velocity: f32,
-    pub(crate) turn_factor: f32,
-    pub(crate) pursuit_factor: f32,
-    pub(crate) pursuit_multiplier: f32,
-    pub(crate) matching_factor: f32,
-    pub(crate) self_avoid_factor: f32,
-    pub(crate) visual_range: f32,
-    pub(crate) protected_range: f32,
-    pub(crate) interest_range: f32,
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct GridParams {
+        pub(crate) grid: u32,
+        // Lines through the world origin
+        pub(crate) axes: u32,
+        // The MIN/MAX_SCREEN rectangle agents turn back at
+        pub(crate) boundary: u32,
+        // Drawn with its label by scale_bar.rs on top of the window frame, unused by the shader
+        pub(crate) scale_bar: u32,
+        // Grid lines are the smallest 1, 2 or 5 times a power of ten world units at least this
+        // many points apart
+        pub(crate) min_spacing: f32,
+        pub(crate) opacity: f32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct DensityParams {
+        pub(crate) mode: u32,
+        // Standard deviation of the gaussian each boid is splatted with, in world units
+        pub(crate) kernel_radius: f32,
+        // Steps the running average is taken over
+        pub(crate) average_frames: f32,
+        pub(crate) show_average: u32,
+        // Density drawn at the top of the colour map
+        pub(crate) max_density: f32,
+        pub(crate) opacity: f32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct OverlayParams {
+        // interest_range and protected_range circles around every predator
+        pub(crate) predator_ranges: u32,
+        // Predator to pursued boid, from pursuits_buf
+        pub(crate) pursuit_lines: u32,
+        // Flock centre, lead boid and outermost boid from StrategyTargets
+        pub(crate) strategy_targets: u32,
+        // visual_range and protected_range circles around boid_id
+        pub(crate) boid_ranges: u32,
+        pub(crate) boid_id: u32,
+        // In points, so lines stay readable at any zoom
+        pub(crate) line_width: f32,
+        // SelectionKind and index of the agent picked in the window, ringed whatever else is on
+        pub(crate) selection: u32,
+        pub(crate) selected_id: u32,
+    }
+}
+
+// What the predator strategies in predator_chase_path.wgsl aim for, written every step
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct StrategyTargets {
+        pub(crate) flock_center: [f32; 2],
+        pub(crate) lead_id: u32,
+        pub(crate) outermost_id: u32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct ColorParams {
+        // Cosine palette coefficients of the current ColorMap, see ColorMap::coefficients
+        pub(crate) offset: [f32; 4],
+        pub(crate) amplitude: [f32; 4],
+        pub(crate) frequency: [f32; 4],
+        pub(crate) phase: [f32; 4],
+        pub(crate) mode: u32,
+        // Values of the colour mode mapped to the start and end of the colour map
+        pub(crate) min_value: f32,
+        pub(crate) max_value: f32,
+        pub(crate) _padding: f32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct TrailParams {
+        // Only rgb is used, vec3 would be padded out to 16 bytes anyway
+        pub(crate) boid_color: [f32; 4],
+        pub(crate) predator_color: [f32; 4],
+        // Frames until a trail has faded to 1%
+        pub(crate) boid_length: f32,
+        pub(crate) predator_length: f32,
+        pub(crate) enabled: u32,
+        pub(crate) _padding: f32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct PostParams {
+        pub(crate) bloom: u32,
+        // Luminance above which pixels start to bloom
+        pub(crate) bloom_threshold: f32,
+        pub(crate) bloom_intensity: f32,
+        // Blur tap spacing in half resolution texels
+        pub(crate) bloom_radius: f32,
+        pub(crate) tone_mapping: u32,
+        // In stops, applied before tone mapping
+        pub(crate) exposure: f32,
+        pub(crate) vignette: u32,
+        pub(crate) vignette_strength: f32,
+        // Distance from the centre where darkening starts, 1 is the corners
+        pub(crate) vignette_radius: f32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct Boid {
+        pub(crate) pos: [f32; 2],
+        pub(crate) vel: [f32; 2],
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct BoidParams {
+        pub(crate) max_velocity: f32,
+        pub(crate) min_velocity: f32,
+        pub(crate) turn_factor: f32,
+        pub(crate) visual_range: f32,
+        pub(crate) protected_range: f32,
+        pub(crate) centering_factor: f32,
+        pub(crate) self_avoid_factor: f32,
+        pub(crate) predator_avoid_factor: f32,
+        pub(crate) matching_factor: f32,
+        pub(crate) alarm_spread: f32,
+        // Steps for alarm to rise to the neighbours level, each step closes 1 / alarm_delay of it
+        pub(crate) alarm_delay: f32,
+        pub(crate) alarm_decay: f32,
+        pub(crate) alarm_speed_boost: f32,
+        pub(crate) alarm_separation_boost: f32,
+        pub(crate) min_speed: f32,
+        pub(crate) max_speed: f32,
+        pub(crate) max_force: f32,
+        pub(crate) limit_mode: u32,
+        pub(crate) wander_amplitude: f32,
+        pub(crate) wander_correlation: f32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct PredatorParams {
+        pub(crate) max_velocity: f32,
+        pub(crate) min_velocity: f32,
+        pub(crate) turn_factor: f32,
+        pub(crate) pursuit_factor: f32,
+        pub(crate) pursuit_multiplier: f32,
+        pub(crate) matching_factor: f32,
+        pub(crate) self_avoid_factor: f32,
+        pub(crate) visual_range: f32,
+        pub(crate) protected_range: f32,
+        pub(crate) interest_range: f32,
+        pub(crate) min_speed: f32,
+        pub(crate) max_speed: f32,
+        pub(crate) max_force: f32,
+        pub(crate) limit_mode: u32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct InitParams {
+        pub(crate) boid_formation: u32,
+        pub(crate) predator_placement: u32,
+        pub(crate) num_clusters: u32,
+        pub(crate) spread: f32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct WanderState {
+        pub(crate) rng_state: [u32; 4],
+        pub(crate) force: [f32; 2],
+        pub(crate) _padding: [f32; 2],
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct FoodPatch {
+        pub(crate) pos: [f32; 2],
+        pub(crate) amount: f32,
+        pub(crate) radius: f32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct ForageState {
+        // Written by the forage pass, added to the flocking forces before the SAC pass limits them
+        pub(crate) push: [f32; 2],
+        pub(crate) hunger: f32,
+        pub(crate) _padding: f32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct FoodParams {
+        pub(crate) hunger_rate: f32,
+        pub(crate) hunger_threshold: f32,
+        pub(crate) foraging_factor: f32,
+        pub(crate) eat_rate: f32,
+        pub(crate) regrow_rate: f32,
+        pub(crate) max_food: f32,
+    }
+}
+
+wgsl_struct! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+    pub(crate) struct LeaderControl {
+        // A heading in LeaderMode::Heading, a world position in LeaderMode::Seek
+        pub(crate) target_pos: [f32; 2],
+        pub(crate) influence: f32,
+        pub(crate) mode: u32,
+    }
 }
 
 #[repr(C)]
@@ -139,7 +630,10 @@ pub(crate) struct Layouts {
     pub(crate) uniform_bind_group_layout: BindGroupLayout,
     pub(crate) variable_bind_group_layout: BindGroupLayout,
     pub(crate) compute_bind_group_layout: BindGroupLayout,
+    pub(crate) trail_bind_group_layout: BindGroupLayout,
+    pub(crate) post_bind_group_layout: BindGroupLayout,
     pub(crate) render_pipeline_layout: PipelineLayout,
+    pub(crate) post_pipeline_layout: PipelineLayout,
     pub(crate) compute_pipeline_layout: PipelineLayout,
 }
 
@@ -150,4 +644,303 @@ pub(crate) enum KeyboardControlMode {
     Boids,
     Predator,
     Debug,
+    Leader,
+}
+
+// Stored as a u32 in InitParams.boid_formation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BoidFormation {
+    Rectangle = 0,
+    Gaussian = 1,
+    Ring = 2,
+    Clusters = 3,
+    Lattice = 4,
+    Vortex = 5,
+    // Rectangle on the gpu, then overwritten from Controls.formation_csv
+    Csv = 6,
+}
+
+impl BoidFormation {
+    pub(crate) const ALL: [BoidFormation; 7] = [
+        BoidFormation::Rectangle,
+        BoidFormation::Gaussian,
+        BoidFormation::Ring,
+        BoidFormation::Clusters,
+        BoidFormation::Lattice,
+        BoidFormation::Vortex,
+        BoidFormation::Csv,
+    ];
+}
+
+// Stored as a u32 in InitParams.predator_placement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PredatorPlacement {
+    Random = 0,
+    Edges = 1,
+    InFlock = 2,
+}
+
+impl PredatorPlacement {
+    pub(crate) const ALL: [PredatorPlacement; 3] = [
+        PredatorPlacement::Random,
+        PredatorPlacement::Edges,
+        PredatorPlacement::InFlock,
+    ];
+}
+
+// Stored as a u32 in LeaderControl.mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LeaderMode {
+    // Boid 0 flocks like any other boid
+    Off = 0,
+    // Boid 0 steers along LeaderControl.target_pos
+    Heading = 1,
+    // Boid 0 steers towards the world position in LeaderControl.target_pos
+    Seek = 2,
+}
+
+impl LeaderMode {
+    pub(crate) const ALL: [LeaderMode; 3] =
+        [LeaderMode::Off, LeaderMode::Heading, LeaderMode::Seek];
+}
+
+// Stored as a u32 in BoidParams/PredatorParams.limit_mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SpeedLimitMode {
+    // Clamp speed as a magnitude and cap the summed steering force at max_force
+    Magnitude = 0,
+    // Clamp each velocity component between min_velocity and max_velocity
+    Component = 1,
+}
+
+impl SpeedLimitMode {
+    pub(crate) const ALL: [SpeedLimitMode; 2] =
+        [SpeedLimitMode::Magnitude, SpeedLimitMode::Component];
+}
+
+// Stored as a u32 in GlyphParams.boid_shape/predator_shape
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum GlyphShape {
+    Arrow = 0,
+    Chevron = 1,
+    Fish = 2,
+}
+
+impl GlyphShape {
+    pub(crate) const ALL: [GlyphShape; 3] =
+        [GlyphShape::Arrow, GlyphShape::Chevron, GlyphShape::Fish];
+}
+
+// Stored as a u32 in GlyphParams.units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum GlyphUnits {
+    // Glyphs scale with the zoom like everything else in the world
+    World = 0,
+    // Glyph sizes are in pixels and stay the same size at any zoom
+    Screen = 1,
+}
+
+impl GlyphUnits {
+    pub(crate) const ALL: [GlyphUnits; 2] = [GlyphUnits::World, GlyphUnits::Screen];
+}
+
+// Stored as a u32 in ColorParams.mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ColorMode {
+    // White, fading to orange with alarm
+    Flat = 0,
+    Speed = 1,
+    // Degrees anticlockwise from +x
+    Heading = 2,
+    // Living boids within visual_range, counted by the SAC pass
+    Neighbours = 3,
+    PredatorDistance = 4,
+    // 1.0 while any predator is pursuing the boid
+    PursuitStatus = 5,
+}
+
+impl ColorMode {
+    pub(crate) const ALL: [ColorMode; 6] = [
+        ColorMode::Flat,
+        ColorMode::Speed,
+        ColorMode::Heading,
+        ColorMode::Neighbours,
+        ColorMode::PredatorDistance,
+        ColorMode::PursuitStatus,
+    ];
+
+    // Sensible min/max values for the colour map in this mode
+    pub(crate) fn default_range(self, bp: &BoidParams, pp: &PredatorParams) -> (f32, f32) {
+        match self {
+            ColorMode::Flat | ColorMode::PursuitStatus => (0.0, 1.0),
+            ColorMode::Speed => (0.0, bp.max_speed * (1.0 + bp.alarm_speed_boost)),
+            ColorMode::Heading => (0.0, 360.0),
+            ColorMode::Neighbours => (0.0, 30.0),
+            ColorMode::PredatorDistance => (0.0, pp.interest_range),
+        }
+    }
+}
+
+// Cosine palettes, colour = offset + amplitude * cos(2pi * (frequency * t + phase))
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ColorMap {
+    Rainbow,
+    Fire,
+    Ocean,
+    Sunset,
+}
+
+impl ColorMap {
+    pub(crate) const ALL: [ColorMap; 4] = [
+        ColorMap::Rainbow,
+        ColorMap::Fire,
+        ColorMap::Ocean,
+        ColorMap::Sunset,
+    ];
+
+    // [offset, amplitude, frequency, phase], alpha is unused
+    pub(crate) fn coefficients(self) -> [[f32; 4]; 4] {
+        match self {
+            ColorMap::Rainbow => [
+                [0.5, 0.5, 0.5, 0.0],
+                [0.5, 0.5, 0.5, 0.0],
+                [1.0, 1.0, 1.0, 0.0],
+                [0.0, 0.33, 0.67, 0.0],
+            ],
+            ColorMap::Fire => [
+                [0.5, 0.3, 0.1, 0.0],
+                [0.5, 0.4, 0.2, 0.0],
+                [0.5, 0.5, 0.5, 0.0],
+                [0.5, 0.6, 0.7, 0.0],
+            ],
+            ColorMap::Ocean => [
+                [0.2, 0.45, 0.6, 0.0],
+                [0.2, 0.35, 0.4, 0.0],
+                [0.5, 0.5, 0.5, 0.0],
+                [0.5, 0.55, 0.55, 0.0],
+            ],
+            ColorMap::Sunset => [
+                [0.5, 0.5, 0.5, 0.0],
+                [0.5, 0.5, 0.5, 0.0],
+                [1.0, 0.7, 0.4, 0.0],
+                [0.0, 0.15, 0.2, 0.0],
+            ],
+        }
+    }
+}
+
+// Stored as a u32 in DensityParams.mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum DensityMode {
+    // The density pass is skipped entirely
+    Off = 0,
+    // Heatmap blended over the agents
+    Overlay = 1,
+    // Heatmap drawn instead of the agents
+    Only = 2,
+}
+
+impl DensityMode {
+    pub(crate) const ALL: [DensityMode; 3] =
+        [DensityMode::Off, DensityMode::Overlay, DensityMode::Only];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum VideoFormat {
+    // Uncompressed 4:4:4 frames, lossless and readable by ffmpeg and most players
+    Y4m,
+    // Quantised to 256 colours per frame, small enough to share directly
+    Gif,
+}
+
+impl VideoFormat {
+    pub(crate) const ALL: [VideoFormat; 2] = [VideoFormat::Y4m, VideoFormat::Gif];
+
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            VideoFormat::Y4m => "y4m",
+            VideoFormat::Gif => "gif",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RecordSource {
+    // Same view as the window at its current framebuffer size
+    Window,
+    // Same view rendered at Recorder::size
+    Offscreen,
+}
+
+impl RecordSource {
+    pub(crate) const ALL: [RecordSource; 2] = [RecordSource::Window, RecordSource::Offscreen];
+}
+
+// Stored as a u32 in PostParams.tone_mapping
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ToneMapping {
+    // Values above 1 are clipped
+    Off = 0,
+    Reinhard = 1,
+    Aces = 2,
+}
+
+impl ToneMapping {
+    pub(crate) const ALL: [ToneMapping; 3] =
+        [ToneMapping::Off, ToneMapping::Reinhard, ToneMapping::Aces];
+}
+
+// A is drawn on the left and B on the right
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ComparisonSide {
+    A,
+    B,
+}
+
+impl ComparisonSide {
+    pub(crate) const ALL: [ComparisonSide; 2] = [ComparisonSide::A, ComparisonSide::B];
+
+    pub(crate) fn other(self) -> ComparisonSide {
+        match self {
+            ComparisonSide::A => ComparisonSide::B,
+            ComparisonSide::B => ComparisonSide::A,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FollowMode {
+    Off,
+    Predator,
+    Boid,
+    // Mean position of the boids that haven't been captured
+    Centroid,
+    // The boid the chosen predator is pursuing, or the predator while it has none
+    PredatorTarget,
+}
+
+impl FollowMode {
+    pub(crate) const ALL: [FollowMode; 5] = [
+        FollowMode::Off,
+        FollowMode::Predator,
+        FollowMode::Boid,
+        FollowMode::Centroid,
+        FollowMode::PredatorTarget,
+    ];
+}
+
+// Stored as a u32 in OverlayParams.selection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SelectionKind {
+    None = 0,
+    Boid = 1,
+    Predator = 2,
+}
+
+impl SelectionKind {
+    pub(crate) const ALL: [SelectionKind; 3] = [
+        SelectionKind::None,
+        SelectionKind::Boid,
+        SelectionKind::Predator,
+    ];
 }
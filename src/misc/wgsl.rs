@@ -0,0 +1,465 @@
+use std::{collections::HashSet, mem::size_of};
+
+use nannou::wgpu;
+
+use crate::{
+    Boid, BoidFormation, BoidParams, ColorMode, ColorParams, DensityMode, DensityParams,
+    FoodParams, FoodPatch, ForageState, GlyphParams, GlyphShape, GlyphUnits, GridParams,
+    InitParams, LeaderControl, LeaderMode, OverlayParams, PostParams, PredatorParams,
+    PredatorPlacement, ResolutionUniform, SelectionKind, SpeedLimitMode, StrategyTargets,
+    ToneMapping, TrailParams, ViewParams, WanderState, DENSITY_GRID_X, DENSITY_GRID_Y,
+    DENSITY_MARGIN, LEADER_ID, MAX_SCREEN_X, MAX_SCREEN_Y, MIN_SCREEN_X, MIN_SCREEN_Y, NUM_BOIDS,
+    NUM_FOOD_PATCHES, NUM_PREDATORS,
+};
+
+// Name of the include that expands to shared_definitions()
+const SHARED_INCLUDE: &str = "shared";
+
+// Every shader source under src/shaders, keyed by its path relative to that directory
+const SHADER_SOURCES: &[(&str, &str)] = &[
+    (
+        "common/random.wgsl",
+        include_str!("../shaders/common/random.wgsl"),
+    ),
+    (
+        "common/view.wgsl",
+        include_str!("../shaders/common/view.wgsl"),
+    ),
+    (
+        "common/glyphs.wgsl",
+        include_str!("../shaders/common/glyphs.wgsl"),
+    ),
+    (
+        "common/density.wgsl",
+        include_str!("../shaders/common/density.wgsl"),
+    ),
+    (
+        "common/steering.wgsl",
+        include_str!("../shaders/common/steering.wgsl"),
+    ),
+    ("vertex/v2.wgsl", include_str!("../shaders/vertex/v2.wgsl")),
+    (
+        "fragment/boids_frag.wgsl",
+        include_str!("../shaders/fragment/boids_frag.wgsl"),
+    ),
+    (
+        "fragment/trails.wgsl",
+        include_str!("../shaders/fragment/trails.wgsl"),
+    ),
+    (
+        "fragment/post.wgsl",
+        include_str!("../shaders/fragment/post.wgsl"),
+    ),
+    (
+        "compute/init_boids.wgsl",
+        include_str!("../shaders/compute/init_boids.wgsl"),
+    ),
+    (
+        "compute/init_predator.wgsl",
+        include_str!("../shaders/compute/init_predator.wgsl"),
+    ),
+    (
+        "compute/seperation_alignment_cohesion.wgsl",
+        include_str!("../shaders/compute/seperation_alignment_cohesion.wgsl"),
+    ),
+    (
+        "compute/predator_chase_path.wgsl",
+        include_str!("../shaders/compute/predator_chase_path.wgsl"),
+    ),
+    (
+        "compute/forage.wgsl",
+        include_str!("../shaders/compute/forage.wgsl"),
+    ),
+    (
+        "compute/density.wgsl",
+        include_str!("../shaders/compute/density.wgsl"),
+    ),
+];
+
+pub(crate) trait WgslType {
+    const WGSL: &'static str;
+}
+
+impl WgslType for f32 {
+    const WGSL: &'static str = "f32";
+}
+
+impl WgslType for u32 {
+    const WGSL: &'static str = "u32";
+}
+
+impl WgslType for [f32; 2] {
+    const WGSL: &'static str = "vec2<f32>";
+}
+
+impl WgslType for [f32; 4] {
+    const WGSL: &'static str = "vec4<f32>";
+}
+
+impl WgslType for [u32; 4] {
+    const WGSL: &'static str = "vec4<u32>";
+}
+
+pub(crate) trait WgslStruct {
+    const WGSL_NAME: &'static str;
+
+    fn wgsl_fields() -> Vec<(&'static str, &'static str)>;
+
+    fn wgsl_offsets() -> Vec<(&'static str, usize)>;
+
+    fn wgsl_declaration() -> String {
+        let mut decl = format!("struct {} {{\n", Self::WGSL_NAME);
+        for (name, ty) in Self::wgsl_fields() {
+            decl.push_str(&format!("  {name}: {ty},\n"));
+        }
+        decl.push_str("}\n");
+        decl
+    }
+}
+
+// Declares a #[repr(C)] struct and records its fields so the WGSL side can be generated from it
+macro_rules! wgsl_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty),*
+        }
+
+        impl $crate::misc::wgsl::WgslStruct for $name {
+            const WGSL_NAME: &'static str = stringify!($name);
+
+            fn wgsl_fields() -> Vec<(&'static str, &'static str)> {
+                vec![$((stringify!($field), <$ty as $crate::misc::wgsl::WgslType>::WGSL)),*]
+            }
+
+            fn wgsl_offsets() -> Vec<(&'static str, usize)> {
+                vec![$((stringify!($field), std::mem::offset_of!($name, $field))),*]
+            }
+        }
+    };
+}
+pub(crate) use wgsl_struct;
+
+// Uniforms that are plain type aliases on the rust side get wrapped in a single field struct
+fn wgsl_wrapper<T: WgslType>(name: &str, field: &str) -> String {
+    format!("struct {name} {{\n  {field}: {},\n}}\n", T::WGSL)
+}
+
+// "InFlock" -> "IN_FLOCK"
+fn upper_snake(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_uppercase());
+    }
+    snake
+}
+
+fn wgsl_enum(prefix: &str, variants: impl IntoIterator<Item = (String, u32)>) -> String {
+    variants
+        .into_iter()
+        .map(|(name, value)| format!("const {prefix}_{}: u32 = {value}u;\n", upper_snake(&name)))
+        .collect()
+}
+
+// A shared struct and the rust layout the tests check its WGSL declaration against
+#[cfg_attr(not(test), allow(dead_code))]
+struct SharedStruct {
+    name: &'static str,
+    declaration: String,
+    size: usize,
+    offsets: Vec<(&'static str, usize)>,
+}
+
+impl SharedStruct {
+    fn of<T: WgslStruct>() -> Self {
+        Self {
+            name: T::WGSL_NAME,
+            declaration: T::wgsl_declaration(),
+            size: size_of::<T>(),
+            offsets: T::wgsl_offsets(),
+        }
+    }
+}
+
+// Every wgsl_struct! the shaders see, in declaration order
+fn shared_structs() -> Vec<SharedStruct> {
+    vec![
+        SharedStruct::of::<Boid>(),
+        SharedStruct::of::<ResolutionUniform>(),
+        SharedStruct::of::<ViewParams>(),
+        SharedStruct::of::<GlyphParams>(),
+        SharedStruct::of::<DensityParams>(),
+        SharedStruct::of::<ColorParams>(),
+        SharedStruct::of::<OverlayParams>(),
+        SharedStruct::of::<GridParams>(),
+        SharedStruct::of::<StrategyTargets>(),
+        SharedStruct::of::<TrailParams>(),
+        SharedStruct::of::<PostParams>(),
+        SharedStruct::of::<BoidParams>(),
+        SharedStruct::of::<PredatorParams>(),
+        SharedStruct::of::<WanderState>(),
+        SharedStruct::of::<InitParams>(),
+        SharedStruct::of::<FoodPatch>(),
+        SharedStruct::of::<ForageState>(),
+        SharedStruct::of::<FoodParams>(),
+        SharedStruct::of::<LeaderControl>(),
+    ]
+}
+
+pub(crate) fn shared_definitions() -> String {
+    let mut wgsl =
+        String::from("// Generated from src/misc/structs.rs, edit the rust side instead\n");
+
+    wgsl.push_str(&format!("const NUM_BOIDS: u32 = {NUM_BOIDS}u;\n"));
+    wgsl.push_str(&format!("const NUM_PREDATORS: u32 = {NUM_PREDATORS}u;\n"));
+    wgsl.push_str(&format!(
+        "const NUM_FOOD_PATCHES: u32 = {NUM_FOOD_PATCHES}u;\n"
+    ));
+    wgsl.push_str(&format!("const LEADER_ID: u32 = {LEADER_ID}u;\n"));
+    wgsl.push_str(&format!("const MAX_SCREEN_X: f32 = {MAX_SCREEN_X:?};\n"));
+    wgsl.push_str(&format!("const MIN_SCREEN_X: f32 = {MIN_SCREEN_X:?};\n"));
+    wgsl.push_str(&format!("const MAX_SCREEN_Y: f32 = {MAX_SCREEN_Y:?};\n"));
+    wgsl.push_str(&format!("const MIN_SCREEN_Y: f32 = {MIN_SCREEN_Y:?};\n"));
+    wgsl.push_str(&format!("const DENSITY_GRID_X: u32 = {DENSITY_GRID_X}u;\n"));
+    wgsl.push_str(&format!("const DENSITY_GRID_Y: u32 = {DENSITY_GRID_Y}u;\n"));
+    wgsl.push_str(&format!(
+        "const DENSITY_MIN: vec2<f32> = vec2<f32>({:?}, {:?});\n",
+        MIN_SCREEN_X - DENSITY_MARGIN,
+        MIN_SCREEN_Y - DENSITY_MARGIN
+    ));
+    wgsl.push_str(&format!(
+        "const DENSITY_MAX: vec2<f32> = vec2<f32>({:?}, {:?});\n",
+        MAX_SCREEN_X + DENSITY_MARGIN,
+        MAX_SCREEN_Y + DENSITY_MARGIN
+    ));
+
+    wgsl.push_str(&wgsl_enum(
+        "LIMIT",
+        SpeedLimitMode::ALL.map(|v| (format!("{v:?}"), v as u32)),
+    ));
+    wgsl.push_str(&wgsl_enum(
+        "LEADER",
+        LeaderMode::ALL.map(|v| (format!("{v:?}"), v as u32)),
+    ));
+    wgsl.push_str(&wgsl_enum(
+        "FORMATION",
+        BoidFormation::ALL.map(|v| (format!("{v:?}"), v as u32)),
+    ));
+    wgsl.push_str(&wgsl_enum(
+        "PLACEMENT",
+        PredatorPlacement::ALL.map(|v| (format!("{v:?}"), v as u32)),
+    ));
+
+    wgsl.push_str(&wgsl_enum(
+        "GLYPH",
+        GlyphShape::ALL.map(|v| (format!("{v:?}"), v as u32)),
+    ));
+    wgsl.push_str(&wgsl_enum(
+        "UNITS",
+        GlyphUnits::ALL.map(|v| (format!("{v:?}"), v as u32)),
+    ));
+
+    wgsl.push_str(&wgsl_enum(
+        "DENSITY",
+        DensityMode::ALL.map(|v| (format!("{v:?}"), v as u32)),
+    ));
+    wgsl.push_str(&wgsl_enum(
+        "COLOR",
+        ColorMode::ALL.map(|v| (format!("{v:?}"), v as u32)),
+    ));
+
+    wgsl.push_str(&wgsl_enum(
+        "TONE_MAP",
+        ToneMapping::ALL.map(|v| (format!("{v:?}"), v as u32)),
+    ));
+    wgsl.push_str(&wgsl_enum(
+        "SELECT",
+        SelectionKind::ALL.map(|v| (format!("{v:?}"), v as u32)),
+    ));
+
+    wgsl.push_str(&wgsl_wrapper::<crate::TimeUniform>("TimeUniform", "time"));
+    wgsl.push_str(&wgsl_wrapper::<crate::SeedUniform>("SeedUniform", "seed"));
+
+    for shared in shared_structs() {
+        wgsl.push_str(&shared.declaration);
+    }
+
+    wgsl
+}
+
+pub(crate) fn embedded_source(path: &str) -> Option<String> {
+    SHADER_SOURCES
+        .iter()
+        .find(|(p, _)| *p == path)
+        .map(|(_, src)| src.to_string())
+}
+
+// Expands `#include "path"` lines, each module is pasted in at most once
+pub(crate) fn compose_shader(
+    path: &str,
+    load: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut included = HashSet::new();
+    let mut out = String::new();
+    expand_includes(path, load, &mut included, &mut out)?;
+    Ok(out)
+}
+
+fn expand_includes(
+    path: &str,
+    load: &dyn Fn(&str) -> Option<String>,
+    included: &mut HashSet<String>,
+    out: &mut String,
+) -> Result<(), String> {
+    if !included.insert(path.to_string()) {
+        return Ok(());
+    }
+
+    if path == SHARED_INCLUDE {
+        out.push_str(&shared_definitions());
+        return Ok(());
+    }
+
+    let src = load(path).ok_or_else(|| format!("shader module not found: {path}"))?;
+
+    for line in src.lines() {
+        match line.trim().strip_prefix("#include") {
+            Some(rest) => {
+                let include = rest.trim().trim_matches('"');
+                expand_includes(include, load, included, out)
+                    .map_err(|e| format!("{e}\n  included from {path}"))?;
+            }
+            None => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn create_shader_module(device: &wgpu::Device, path: &str) -> wgpu::ShaderModule {
+    let source = compose_shader(path, &embedded_source).unwrap();
+    shader_module_from_source(device, path, source)
+}
+
+pub(crate) fn shader_module_from_source(
+    device: &wgpu::Device,
+    path: &str,
+    source: String,
+) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(path),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::wgpu::naga;
+
+    use super::*;
+    use crate::updates::hot_reload::PIPELINE_SHADERS;
+
+    fn parse(path: &str, source: &str) -> naga::Module {
+        naga::front::wgsl::parse_str(source)
+            .unwrap_or_else(|e| panic!("{path}: {}", e.emit_to_string_with_path(source, path)))
+    }
+
+    #[test]
+    fn pipeline_shaders_compose_and_validate() {
+        for path in PIPELINE_SHADERS {
+            let source = compose_shader(path, &embedded_source).unwrap();
+            let module = parse(path, &source);
+
+            naga::valid::Validator::new(
+                naga::valid::ValidationFlags::all(),
+                naga::valid::Capabilities::empty(),
+            )
+            .validate(&module)
+            .unwrap_or_else(|e| panic!("{path}: {e:?}"));
+        }
+    }
+
+    // A layout mismatch would silently shift every field after it on the GPU side
+    #[test]
+    fn struct_layouts_match_rust() {
+        let module = parse(SHARED_INCLUDE, &shared_definitions());
+        let mut layouter = naga::proc::Layouter::default();
+        layouter.update(module.to_ctx()).unwrap();
+
+        for shared in shared_structs() {
+            let (handle, ty) = module
+                .types
+                .iter()
+                .find(|(_, ty)| ty.name.as_deref() == Some(shared.name))
+                .unwrap_or_else(|| panic!("{} isn't declared", shared.name));
+            assert_eq!(
+                layouter[handle].size as usize, shared.size,
+                "{}",
+                shared.name
+            );
+
+            let naga::TypeInner::Struct { members, .. } = &ty.inner else {
+                panic!("{} isn't a struct", shared.name);
+            };
+            assert_eq!(members.len(), shared.offsets.len(), "{}", shared.name);
+
+            for (member, (field, offset)) in members.iter().zip(&shared.offsets) {
+                assert_eq!(member.name.as_deref(), Some(*field), "{}", shared.name);
+                assert_eq!(member.offset as usize, *offset, "{}.{field}", shared.name);
+            }
+        }
+    }
+
+    #[test]
+    fn upper_snake_splits_on_capitals() {
+        assert_eq!(upper_snake("InFlock"), "IN_FLOCK");
+        assert_eq!(upper_snake("Off"), "OFF");
+        assert_eq!(upper_snake("PredatorTarget"), "PREDATOR_TARGET");
+    }
+
+    fn load_from(
+        modules: &'static [(&'static str, &'static str)],
+    ) -> impl Fn(&str) -> Option<String> {
+        move |path| {
+            modules
+                .iter()
+                .find(|(p, _)| *p == path)
+                .map(|(_, src)| src.to_string())
+        }
+    }
+
+    #[test]
+    fn includes_are_expanded_once() {
+        let load = load_from(&[
+            (
+                "main.wgsl",
+                "#include \"a.wgsl\"\n#include \"b.wgsl\"\nfn main() {}",
+            ),
+            ("a.wgsl", "#include \"b.wgsl\"\nfn a() {}"),
+            ("b.wgsl", "fn b() {}"),
+        ]);
+
+        let source = compose_shader("main.wgsl", &load).unwrap();
+        assert_eq!(source, "fn b() {}\nfn a() {}\nfn main() {}\n");
+    }
+
+    #[test]
+    fn missing_include_is_an_error() {
+        let load = load_from(&[("main.wgsl", "#include \"gone.wgsl\"\nfn main() {}")]);
+
+        let error = compose_shader("main.wgsl", &load).unwrap_err();
+        assert!(error.contains("gone.wgsl"), "{error}");
+        assert!(error.contains("included from main.wgsl"), "{error}");
+    }
+}
@@ -0,0 +1,10 @@
+// The density grid spans DENSITY_MIN -> DENSITY_MAX, the world bounds plus a margin
+
+fn density_cell_size() -> vec2<f32> {
+  return (DENSITY_MAX - DENSITY_MIN) / vec2<f32>(f32(DENSITY_GRID_X), f32(DENSITY_GRID_Y));
+}
+
+// World position of a cell's centre
+fn density_cell_pos(cell: vec2<u32>) -> vec2<f32> {
+  return DENSITY_MIN + (vec2<f32>(cell) + 0.5) * density_cell_size();
+}
@@ -0,0 +1,63 @@
+// Velocity oriented agent glyphs, pure functions of the position being shaded
+
+fn in_triangle(p: vec2<f32>, v0: vec2<f32>, v1: vec2<f32>, v2: vec2<f32>) -> bool {
+    // Calculate barycentric coordinates
+    let rv0 = v1 - v0;
+    let rv1 = v2 - v0;
+    let rv2 = p - v0;
+
+    let d00 = dot(rv0, rv0);
+    let d01 = dot(rv0, rv1);
+    let d11 = dot(rv1, rv1);
+    let d20 = dot(rv2, rv0);
+    let d21 = dot(rv2, rv1);
+    let inv_denom = 1.0 / (d00 * d11 - d01 * d01);
+    let v = (d11 * d20 - d01 * d21) * inv_denom;
+    let w = (d00 * d21 - d01 * d20) * inv_denom;
+    let u = 1.0 - v - w;
+
+    return (u >= 0.0) && (v >= 0.0) && (w >= 0.0);
+}
+
+fn dist_to_segment(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let ap = p - a;
+    let ab = b - a;
+    let h = clamp(dot(ap, ab) / dot(ab, ab), 0.0, 1.0);
+    return length(ap - ab * h);
+}
+
+// Glyph shapes in a local frame where +y is the heading and the glyph spans -1.0 -> 1.0,
+// they are all mirror symmetric so only x >= 0.0 is tested
+fn in_glyph(local: vec2<f32>, shape: u32) -> bool {
+    let p = vec2<f32>(abs(local.x), local.y);
+
+    if (shape == GLYPH_CHEVRON) {
+        return dist_to_segment(p, vec2(0.0, 1.0), vec2(0.7, -0.8)) < 0.22;
+    } else if (shape == GLYPH_FISH) {
+        let body = vec2<f32>(p.x / 0.42, (p.y - 0.25) / 0.75);
+        let tail = in_triangle(p, vec2(0.0, -0.4), vec2(0.5, -1.0), vec2(0.0, -0.75));
+        return dot(body, body) < 1.0 || tail;
+    }
+
+    // Arrow head with a notch cut out of the back
+    return in_triangle(p, vec2(0.0, 1.0), vec2(0.65, -1.0), vec2(0.0, -0.45));
+}
+
+// Coverage of a glyph of the given world size centred on the agent and pointing along its velocity
+fn glyph(uv: vec2<f32>, agent: Boid, scale: f32, shape: u32) -> f32 {
+    let d = (uv - agent.pos) / scale;
+
+    // Cheap reject before building the local frame
+    if (dot(d, d) > 2.0) {
+        return 0.0;
+    }
+
+    // Agents that have come to a stop keep pointing up
+    var heading = vec2<f32>(0.0, 1.0);
+    if (length(agent.vel) > 0.00001) {
+        heading = normalize(agent.vel);
+    }
+    let side = vec2<f32>(heading.y, -heading.x);
+
+    return select(0.0, 1.0, in_glyph(vec2(dot(d, side), dot(d, heading)), shape));
+}
@@ -0,0 +1,28 @@
+struct RandomResult {
+  state: vec4<u32>,
+  value: f32,
+}
+
+fn taus_step(z: u32, S1: u32, S2: u32, S3: u32, M: u32) -> u32 {
+  let b = (((z << S1) ^ z) >> S2);
+  return ((z & M) << S3) ^ b;
+}
+
+fn lcg_step(z: u32, A: u32, C: u32) -> u32 {
+  return A * z + C;
+}
+
+// Returns the next generator state along with a value in -1.0 -> 1.0
+fn hybrid_taus(st: vec4<u32>) -> RandomResult {
+  var state = st;
+  state.x = taus_step(state.x, 13u, 19u, 12u, 4294967294u);
+  state.y = taus_step(state.y, 2u, 25u, 4u, 4294967288u);
+  state.z = taus_step(state.z, 3u, 11u, 17u, 4294967280u);
+  state.w = lcg_step(state.w, 1664525u, 1013904223u);
+
+  var rand: RandomResult;
+  rand.state = state;
+  rand.value = 2.0 * f32(state.x ^ state.y ^ state.z ^ state.w) / f32(0xFFFFFFFFu) - 1.0;
+
+  return rand;
+}
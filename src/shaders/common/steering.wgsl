@@ -0,0 +1,11 @@
+// Steering limits shared by the boid and predator passes, mode is a LIMIT_* value
+
+fn limit_force(dv: vec2<f32>, max_force: f32, mode: u32) -> vec2<f32> {
+  let force = length(dv);
+
+  if (mode == LIMIT_COMPONENT || force <= max_force) {
+    return dv;
+  }
+
+  return dv * (max_force / force);
+}
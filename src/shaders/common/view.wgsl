@@ -0,0 +1,66 @@
+// Bindings and screen -> world mapping shared by every render pipeline
+
+@group(1) @binding(0)
+var<uniform> tu: TimeUniform;
+@group(1) @binding(1)
+var<uniform> ru: ResolutionUniform;
+
+@group(2) @binding(0)
+var<storage, read_write> pa: ViewParams;
+@group(2) @binding(1)
+var<uniform> gp: GlyphParams;
+
+@group(3) @binding(0)
+var trail_tex: texture_2d<f32>;
+@group(3) @binding(1)
+var<uniform> tp: TrailParams;
+
+// ASPECT RATIO
+fn scale_aspect(fc: vec2<f32>) -> vec2<f32> {
+  // Scale from 0.0 --> 1.0 to -1.0 --> 1.0, relative to the pane when the view is split
+  var uv: vec2<f32> = (((fc - ru.origin) * 2.0) - ru.xy) / max(ru.xy.x, ru.xy.y);
+  uv.y *= -1.0;
+  return uv;
+}
+
+fn world_pos(fc: vec2<f32>) -> vec2<f32> {
+  var uv: vec2<f32> = scale_aspect(fc); // Scale to -1.0 -> 1.0 + fix aspect ratio
+  uv.x += pa.x_shift * pa.zoom;
+  uv.y += pa.y_shift * pa.zoom;
+  return uv / pa.zoom;
+}
+
+// World units per logical point at the current zoom, so screen sized glyphs look the same at
+// any DPI scale
+fn pixel_size() -> f32 {
+  return 2.0 * ru.scale_factor / (max(ru.xy.x, ru.xy.y) * pa.zoom);
+}
+
+// Smallest 1, 2 or 5 times a power of ten world units that is at least min_points apart on
+// screen, grid_spacing in scale_bar.rs has to agree
+fn grid_spacing(min_points: f32) -> f32 {
+  let min_world = max(min_points, 1.0) * pixel_size();
+  let step = pow(10.0, floor(log2(min_world) / log2(10.0)));
+
+  if (step >= min_world) {
+    return step;
+  } else if (2.0 * step >= min_world) {
+    return 2.0 * step;
+  } else if (5.0 * step >= min_world) {
+    return 5.0 * step;
+  }
+  return 10.0 * step;
+}
+
+// Glyph sizes are either world units or points depending on GlyphParams.units
+fn glyph_scale(size: f32) -> f32 {
+  if (gp.units == UNITS_SCREEN) {
+    return size * pixel_size();
+  }
+  return size;
+}
+
+fn trail_sample(fc: vec2<f32>) -> vec4<f32> {
+  let max_px = vec2<i32>(textureDimensions(trail_tex)) - 1;
+  return textureLoad(trail_tex, clamp(vec2<i32>(fc), vec2<i32>(0), max_px), 0);
+}
@@ -0,0 +1,34 @@
+#include "shared"
+#include "common/density.wgsl"
+
+@group(0) @binding(0) var<storage, read_write> boids: array<Boid>;
+@group(0) @binding(7) var<storage, read_write> captured: array<f32>;
+@group(0) @binding(17) var<uniform> dp: DensityParams;
+@group(0) @binding(18) var<storage, read_write> density: array<vec2<f32>>;
+
+// Gathers every boid into the cell through a gaussian kernel, which is the same as splatting each
+// boid into the grid but needs no atomics. x is this step's density, y its running average.
+@compute
+@workgroup_size(8, 8, 1)
+fn splat_density(@builtin(global_invocation_id) id: vec3<u32>) {
+  if (id.x >= DENSITY_GRID_X || id.y >= DENSITY_GRID_Y) {
+    return;
+  }
+
+  let cell = id.y * DENSITY_GRID_X + id.x;
+  let pos = density_cell_pos(id.xy);
+  let r = max(dp.kernel_radius, 0.001);
+
+  var value = 0.0;
+
+  for (var i: u32 = 0u; i < NUM_BOIDS; i++) {
+    let d = distance(pos, boids[i].pos);
+
+    if (d < 3.0 * r) {
+      value += exp(-(d * d) / (2.0 * r * r)) * captured[i];
+    }
+  }
+
+  let average = density[cell].y + (value - density[cell].y) / max(dp.average_frames, 1.0);
+  density[cell] = vec2<f32>(value, average);
+}
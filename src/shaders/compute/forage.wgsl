@@ -0,0 +1,102 @@
+#include "shared"
+
+@group(0) @binding(0) var<storage, read_write> boids: array<Boid>;
+@group(0) @binding(7) var<storage, read_write> captured: array<f32>;
+@group(0) @binding(9) var<storage, read_write> food: array<FoodPatch>;
+@group(0) @binding(10) var<storage, read_write> forage_state: array<ForageState>;
+@group(0) @binding(11) var<storage, read_write> fp: FoodParams;
+@group(0) @binding(12) var<storage, read_write> leader: LeaderControl;
+
+fn in_patch(pos: vec2<f32>, food_patch: FoodPatch) -> bool {
+  return distance(pos, food_patch.pos) < food_patch.radius;
+}
+
+fn find_closest_food(boid: Boid) -> u32 {
+  var closest_id: u32 = 0xFFFFFFFFu;
+  var closest_dist: f32 = 99999999.0;
+
+  for (var i: u32 = 0u; i < NUM_FOOD_PATCHES; i++) {
+    let dist = distance(boid.pos, food[i].pos);
+
+    if (food[i].amount > 0.0 && dist < closest_dist) {
+      closest_dist = dist;
+      closest_id = i;
+    }
+  }
+
+  return closest_id;
+}
+
+fn eat(boid: Boid) -> bool {
+  for (var i: u32 = 0u; i < NUM_FOOD_PATCHES; i++) {
+    if (food[i].amount > 0.0 && in_patch(boid.pos, food[i])) {
+      return true;
+    }
+  }
+
+  return false;
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn forage(@builtin(global_invocation_id) id: vec3<u32>) {
+  // One invocation per boid, so hunger changes by exactly one rate a step
+  if (id.x >= NUM_BOIDS) {
+    return;
+  }
+
+  forage_state[id.x].push = vec2(0.0);
+
+  // Captured boids no longer get hungry
+  if (captured[id.x] == 0.0) {
+    return;
+  }
+
+  var hunger = forage_state[id.x].hunger;
+
+  if (eat(boids[id.x])) {
+    hunger = max(0.0, hunger - fp.eat_rate);
+  } else {
+    hunger = min(1.0, hunger + fp.hunger_rate);
+  }
+  forage_state[id.x].hunger = hunger;
+
+  // A steered leader only follows its controls
+  if (id.x == LEADER_ID && leader.mode != LEADER_OFF) {
+    return;
+  }
+
+  // Hungry boids steer towards the nearest patch that still has food,
+  // harder the hungrier they are, which competes with the flocking rules
+  if (hunger > fp.hunger_threshold) {
+    let food_id = find_closest_food(boids[id.x]);
+
+    if (food_id != 0xFFFFFFFFu) {
+      let offset = food[food_id].pos - boids[id.x].pos;
+
+      // Already on the patch centre, there's no direction to steer in
+      if (length(offset) > 0.0) {
+        forage_state[id.x].push = normalize(offset) * fp.foraging_factor * hunger;
+      }
+    }
+  }
+}
+
+@compute
+@workgroup_size(8, 1, 1)
+fn regrow_food(@builtin(global_invocation_id) id: vec3<u32>) {
+  if (id.x >= NUM_FOOD_PATCHES) {
+    return;
+  }
+
+  // Every boid still hungry inside the patch takes a bite
+  var eaten = 0.0;
+
+  for (var i: u32 = 0u; i < NUM_BOIDS; i++) {
+    if (captured[i] != 0.0 && forage_state[i].hunger > 0.0 && in_patch(boids[i].pos, food[id.x])) {
+      eaten += fp.eat_rate;
+    }
+  }
+
+  food[id.x].amount = clamp(food[id.x].amount - eaten + fp.regrow_rate, 0.0, fp.max_food);
+}
@@ -1,41 +1,75 @@
-struct Boid {
-  pos: vec2<f32>,
-  vel: vec2<f32>,
-}
-@group(0) @binding(0) var<storage, read_write> boids: array<Boid>;
+#include "shared"
+#include "common/random.wgsl"
 
-struct RandomResult {
-    state: vec4<u32>,
-    value: f32,
-};
+const TAU: f32 = 6.28318531;
 
-fn taus_step(z: u32, S1: u32, S2: u32, S3: u32, M: u32) -> u32 {
-    let b = (((z << S1) ^ z) >> S2);
-    return ((z & M) << S3) ^ b;
-}
+@group(0) @binding(0) var<storage, read_write> boids: array<Boid>;
+@group(0) @binding(13) var<storage, read_write> wander: array<WanderState>;
+@group(0) @binding(14) var<uniform> su: SeedUniform;
+@group(0) @binding(15) var<uniform> ip: InitParams;
 
-fn lcg_step(z: u32, A: u32, C: u32) -> u32 {
-    return A * z + C;
+// Box-Muller, turns two uniform values in -1.0 -> 1.0 into a pair of standard normals
+fn gaussian(a: f32, b: f32) -> vec2<f32> {
+  let u = max((a + 1.0) * 0.5, 0.000001);
+  let v = (b + 1.0) * 0.5;
+  return sqrt(-2.0 * log(u)) * vec2<f32>(cos(TAU * v), sin(TAU * v));
 }
 
-fn hybrid_taus(st: vec4<u32>) -> RandomResult {
-    var state = st; 
-    state.x = taus_step(state.x, 13u, 19u, 12u, 4294967294u);
-    state.y = taus_step(state.y, 2u, 25u, 4u, 4294967288u);
-    state.z = taus_step(state.z, 3u, 11u, 17u, 4294967280u);
-    state.w = lcg_step(state.w, 1664525u, 1013904223u);
+fn formation_position(bid: u32, rx: f32, ry: f32) -> vec2<f32> {
+  let spread = ip.spread;
+
+  if (ip.boid_formation == FORMATION_GAUSSIAN) {
+    return gaussian(rx, ry) * spread * 0.5;
+
+  } else if (ip.boid_formation == FORMATION_RING) {
+    let angle = TAU * f32(bid) / f32(NUM_BOIDS);
+    return vec2<f32>(cos(angle), sin(angle)) * spread * (1.0 + 0.05 * rx);
+
+  // Boids are dealt round robin to clusters spaced around an ellipse
+  } else if (ip.boid_formation == FORMATION_CLUSTERS) {
+    let nc = max(ip.num_clusters, 1u);
+    let angle = TAU * f32(bid % nc) / f32(nc);
+    let center = vec2<f32>(cos(angle) * 100.0, sin(angle) * 45.0);
+    return center + gaussian(rx, ry) * spread * 0.15;
 
-    var rand: RandomResult;
-    rand.state = state;
-    rand.value = 2.0  * f32(state.x ^ state.y ^ state.z ^ state.w) / f32(0xFFFFFFFFu) - 1.0;
+  } else if (ip.boid_formation == FORMATION_LATTICE) {
+    let cols = u32(ceil(sqrt(f32(NUM_BOIDS) * 2.0)));
+    let rows = (NUM_BOIDS + cols - 1u) / cols;
+    let cell = vec2<f32>(f32(bid % cols), f32(bid / cols));
+    let spacing = 2.0 * spread / f32(cols);
+    return (cell - vec2<f32>(f32(cols - 1u), f32(rows - 1u)) * 0.5) * spacing;
 
-    return rand;
+  // Uniform over a disc, sqrt keeps the density even towards the rim
+  } else if (ip.boid_formation == FORMATION_VORTEX) {
+    let r = spread * sqrt((rx + 1.0) * 0.5);
+    let angle = TAU * (ry + 1.0) * 0.5;
+    return vec2<f32>(cos(angle), sin(angle)) * r;
+  }
+
+  // FORMATION_RECTANGLE, also the starting point for CSV formations
+  return vec2<f32>(rx * 100.0, ry * 70.0);
+}
+
+fn formation_velocity(pos: vec2<f32>, rvx: f32, rvy: f32) -> vec2<f32> {
+  // Milling boids circle the centre, everyone else starts with a small random velocity
+  if (ip.boid_formation == FORMATION_VORTEX && length(pos) > 0.0) {
+    let tangent = vec2<f32>(-pos.y, pos.x) / length(pos);
+    return tangent * 0.3 + vec2<f32>(rvx, rvy) * 0.02;
+  }
+
+  return vec2<f32>(rvx, rvy) * 0.2;
 }
 
 @compute 
 @workgroup_size(16, 16, 1) 
 fn compute_boid_positions(@builtin(global_invocation_id) id: vec3<u32>) {
-  let seed = id.x * 1000u + id.y * 100u + id.z;
+  // One invocation per boid, otherwise the last writer decides and seeded runs differ
+  if (id.x >= NUM_BOIDS || id.y != 0u || id.z != 0u) {
+    return;
+  }
+
+  // Seed 0 reproduces the original per-id seeds
+  let seed = id.x * 1000u + id.y * 100u + id.z + su.seed * 2654435761u;
   let state = vec4<u32>(seed, seed + 1u, seed + 2u, seed + 3u);
 
   // Random pos(x,y)
@@ -45,6 +79,12 @@ fn compute_boid_positions(@builtin(global_invocation_id) id: vec3<u32>) {
   var rvx: RandomResult = hybrid_taus(ry.state);
   var rvy: RandomResult = hybrid_taus(rvx.state);
 
-  boids[id.x].pos = vec2<f32>(rx.value * 100.0, ry.value * 70.0);
-  boids[id.x].vel = vec2<f32>(rvx.value, rvy.value) * 0.2;
+  boids[id.x].pos = formation_position(id.x, rx.value, ry.value);
+  boids[id.x].vel = formation_velocity(boids[id.x].pos, rvx.value, rvy.value);
+
+  // Hand the generator on to the SAC pass so each boid keeps its own stream.
+  // The taus steps need each component above 128 to avoid degenerate sequences.
+  var rw: RandomResult = hybrid_taus(rvy.state);
+  wander[id.x].rng_state = rw.state | vec4<u32>(128u);
+  wander[id.x].force = vec2(0.0);
 }
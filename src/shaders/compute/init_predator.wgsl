@@ -1,41 +1,45 @@
-struct Boid {
-  pos: vec2<f32>,
-  vel: vec2<f32>,
-}
-@group(0) @binding(2) var<storage, read_write> predators: array<Boid>;
-
-struct RandomResult {
-    state: vec4<u32>,
-    value: f32,
-};
+#include "shared"
+#include "common/random.wgsl"
 
-fn taus_step(z: u32, S1: u32, S2: u32, S3: u32, M: u32) -> u32 {
-    let b = (((z << S1) ^ z) >> S2);
-    return ((z & M) << S3) ^ b;
-}
-
-fn lcg_step(z: u32, A: u32, C: u32) -> u32 {
-    return A * z + C;
-}
-
-fn hybrid_taus(st: vec4<u32>) -> RandomResult {
-    var state = st; 
-    state.x = taus_step(state.x, 13u, 19u, 12u, 4294967294u);
-    state.y = taus_step(state.y, 2u, 25u, 4u, 4294967288u);
-    state.z = taus_step(state.z, 3u, 11u, 17u, 4294967280u);
-    state.w = lcg_step(state.w, 1664525u, 1013904223u);
-
-    var rand: RandomResult;
-    rand.state = state;
-    rand.value = 2.0  * f32(state.x ^ state.y ^ state.z ^ state.w) / f32(0xFFFFFFFFu) - 1.0;
-
-    return rand;
+@group(0) @binding(0) var<storage, read_write> boids: array<Boid>;
+@group(0) @binding(2) var<storage, read_write> predators: array<Boid>;
+@group(0) @binding(14) var<uniform> su: SeedUniform;
+@group(0) @binding(15) var<uniform> ip: InitParams;
+
+// Runs after compute_boid_positions, so the flock is already in place
+fn placement_position(pid: u32, rx: f32, ry: f32) -> vec2<f32> {
+  if (ip.predator_placement == PLACEMENT_EDGES) {
+    // Walk the screen edges, one predator per side
+    let side = pid % 4u;
+    if (side == 0u) {
+      return vec2<f32>(rx * MAX_SCREEN_X, MAX_SCREEN_Y);
+    } else if (side == 1u) {
+      return vec2<f32>(MAX_SCREEN_X, ry * MAX_SCREEN_Y);
+    } else if (side == 2u) {
+      return vec2<f32>(rx * MAX_SCREEN_X, -MAX_SCREEN_Y);
+    }
+    return vec2<f32>(-MAX_SCREEN_X, ry * MAX_SCREEN_Y);
+  }
+
+  if (ip.predator_placement == PLACEMENT_IN_FLOCK) {
+    // Drop each predator on top of a randomly chosen boid
+    let bid = u32((rx + 1.0) * 0.5 * f32(NUM_BOIDS - 1u));
+    return boids[bid].pos + vec2<f32>(rx, ry);
+  }
+
+  return vec2<f32>(rx * 150.0, ry * 70.0);
 }
 
 @compute 
 @workgroup_size(4, 1, 1) 
 fn compute_predator_position(@builtin(global_invocation_id) id: vec3<u32>) {
-  let seed = id.x * 1000u + id.y * 100u + id.z;
+  // One invocation per predator, otherwise the last writer decides and seeded runs differ
+  if (id.x >= NUM_PREDATORS) {
+    return;
+  }
+
+  // Seed 0 reproduces the original per-id seeds
+  let seed = id.x * 1000u + id.y * 100u + id.z + su.seed * 2654435761u;
   let state = vec4<u32>(seed, seed + 1u, seed + 2u, seed + 3u);
 
   // Random pos(x,y)
@@ -45,6 +49,6 @@ fn compute_predator_position(@builtin(global_invocation_id) id: vec3<u32>) {
   var rvx: RandomResult = hybrid_taus(ry.state);
   var rvy: RandomResult = hybrid_taus(rvx.state);
 
-  predators[id.x].pos = vec2<f32>(rx.value * 150.0, ry.value * 70.0);
+  predators[id.x].pos = placement_position(id.x, rx.value, ry.value);
   predators[id.x].vel = vec2<f32>(rvx.value, rvy.value) * 0.2;
 }
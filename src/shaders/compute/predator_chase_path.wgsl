@@ -1,31 +1,10 @@
-const NUM_BOIDS: u32 = 255u;
-const NUM_PREDATORS: u32 = 4u;
-
-const MAX_SCREEN_X: f32 = 150.0;
-const MIN_SCREEN_X: f32 = -150.0;
-const MAX_SCREEN_Y: f32 = 70.0;
-const MIN_SCREEN_Y: f32 = -70.0;
+#include "shared"
+#include "common/steering.wgsl"
 
 struct PreyData {
   id: u32,
   dist: f32,
 }
-struct Boid {
-  pos: vec2<f32>,
-  vel: vec2<f32>,
-}
-struct PredatorParams {
-  max_velocity: f32,
-  min_velocity: f32,
-  turn_factor: f32,
-  pursuit_factor: f32,
-  pursuit_multiplier: f32,
-  matching_factor: f32,
-  self_avoid_factor: f32,
-  visual_range: f32,
-  protected_range: f32,
-  interest_range: f32,
-}
 
 @group(0) @binding(0) var<storage, read_write> boids: array<Boid>;
 @group(0) @binding(2) var<storage, read_write> predators: array<Boid>;
@@ -33,6 +12,7 @@ struct PredatorParams {
 @group(0) @binding(5) var<storage, read_write> pursuits: array<u32>;
 @group(0) @binding(6) var<storage, read_write> captures: array<u32>;
 @group(0) @binding(7) var<storage, read_write> captured: array<f32>;
+@group(0) @binding(19) var<storage, read_write> strategy: StrategyTargets;
 
 fn seperation(predator: Boid) -> vec2<f32> {
   var ip = predator;
@@ -163,7 +143,17 @@ fn respect_screen_edges(predator: Boid) -> vec2<f32> {
 }
 
 fn respect_speed_limit(predator: Boid) -> vec2<f32> {
-  return clamp(predator.vel, vec2(pp.min_velocity), vec2(pp.max_velocity));
+  if (pp.limit_mode == LIMIT_COMPONENT) {
+    return clamp(predator.vel, vec2(pp.min_velocity), vec2(pp.max_velocity));
+  }
+
+  let speed = length(predator.vel);
+
+  if (speed == 0.0) {
+    return predator.vel;
+  }
+
+  return predator.vel * (clamp(speed, pp.min_speed, pp.max_speed) / speed);
 }
 
 fn check_captures(pid: u32, predator: Boid) {
@@ -181,6 +171,15 @@ fn check_captures(pid: u32, predator: Boid) {
 @compute 
 @workgroup_size(4, 1, 1) 
 fn compute_predator_pursuit(@builtin(global_invocation_id) id: vec3<u32>) {
+  var dv = vec2(0.0);
+
+  // Every strategy's target, whether or not a predator is using it, for the debug overlays
+  if (id.x == 0u) {
+    strategy.flock_center = find_flock_center();
+    strategy.lead_id = find_lead_boid();
+    strategy.outermost_id = find_outermost_boid();
+  }
+
   // If already in pursuit continue;
   if (pursuits[id.x] != 0xFFFFFFFFu) {
     let chasing_id = pursuits[id.x];
@@ -188,8 +187,8 @@ fn compute_predator_pursuit(@builtin(global_invocation_id) id: vec3<u32>) {
     if (distance(boids[chasing_id].pos, predators[id.x].pos) > pp.interest_range) {
       pursuits[id.x] = 0xFFFFFFFFu;
     } else {
-      predators[id.x].vel += get_prey_direction(boids[chasing_id].pos, predators[id.x])*pp.pursuit_multiplier;
-      predators[id.x].vel += match_velocity(boids[chasing_id].vel, predators[id.x])*pp.pursuit_multiplier;
+      dv += get_prey_direction(boids[chasing_id].pos, predators[id.x])*pp.pursuit_multiplier;
+      dv += match_velocity(boids[chasing_id].vel, predators[id.x])*pp.pursuit_multiplier;
     }
   } else {
     let closest_id: u32 = find_closest_boid(predators[id.x]);
@@ -197,26 +196,26 @@ fn compute_predator_pursuit(@builtin(global_invocation_id) id: vec3<u32>) {
     // Predator 0 targets center of mass of flock 
     if (id.x == 0u) {
       let fc = find_flock_center();
-      predators[id.x].vel += get_prey_direction(fc, predators[id.x]);
+      dv += get_prey_direction(fc, predators[id.x]);
 
     // Predator 1 targets the closest boid to itself 
     } else if (id.x == 1u) {
-      predators[id.x].vel += get_prey_direction(closest_boid.pos, predators[id.x]);
-      predators[id.x].vel += match_velocity(closest_boid.vel, predators[id.x]);
+      dv += get_prey_direction(closest_boid.pos, predators[id.x]);
+      dv += match_velocity(closest_boid.vel, predators[id.x]);
 
     // Predator 2 targets the lead boid
     } else if (id.x == 2u) {
       let lead_id: u32 = find_lead_boid();
       let lead_boid = boids[lead_id];
-      predators[id.x].vel += get_prey_direction(lead_boid.pos, predators[id.x]);
-      predators[id.x].vel += match_velocity(lead_boid.vel, predators[id.x]);
+      dv += get_prey_direction(lead_boid.pos, predators[id.x]);
+      dv += match_velocity(lead_boid.vel, predators[id.x]);
 
     // Predator 3 targets the boid that is furthest from the center
     } else {
       let outermost_id: u32 = find_outermost_boid();
       let outermost_boid = boids[outermost_id]; 
-      predators[id.x].vel += get_prey_direction(outermost_boid.pos, predators[id.x]);
-      predators[id.x].vel += match_velocity(outermost_boid.vel, predators[id.x]);
+      dv += get_prey_direction(outermost_boid.pos, predators[id.x]);
+      dv += match_velocity(outermost_boid.vel, predators[id.x]);
     }
 
     // All Predators - If a boid comes within interest range pursue it in next cycle
@@ -226,8 +225,10 @@ fn compute_predator_pursuit(@builtin(global_invocation_id) id: vec3<u32>) {
   }
 
   // Dont bump into each other, exceed screen limits or speed limits
-  predators[id.x].vel += seperation(predators[id.x]);
-  predators[id.x].vel += respect_screen_edges(predators[id.x]);
+  dv += seperation(predators[id.x]);
+  dv += respect_screen_edges(predators[id.x]);
+
+  predators[id.x].vel += limit_force(dv, pp.max_force, pp.limit_mode);
   predators[id.x].vel = respect_speed_limit(predators[id.x]);
 
   predators[id.x].pos += predators[id.x].vel;
@@ -1,50 +1,37 @@
-const NUM_BOIDS: u32 = 255u;
-const NUM_PREDATORS: u32 = 4u;
-
-const MAX_SCREEN_X: f32 = 150.0;
-const MIN_SCREEN_X: f32 = -150.0;
-const MAX_SCREEN_Y: f32 = 70.0;
-const MIN_SCREEN_Y: f32 = -70.0;
+#include "shared"
+#include "common/random.wgsl"
+#include "common/steering.wgsl"
 
 const MAX_BIAS: f32 = 0.01;
 const BIAS_VAL: f32 = 0.001;
 const BIAS_INCREMENT: f32 = 0.00004;
 
-struct Boid {
-  pos: vec2<f32>,
-  vel: vec2<f32>,
-}
-struct BoidParams {
-  max_velocity: f32,
-  min_velocity: f32,
-  turn_factor: f32,
-  visual_range: f32,
-  protected_range: f32,
-  centering_factor: f32,
-  self_avoid_factor: f32,
-  predator_avoid_factor: f32,
-  matching_factor: f32,
-}
-struct TimeUniform {
-  time: f32,
-}
+const LEADER_RESPONSE: f32 = 0.1;
 
 @group(0) @binding(0) var<storage, read_write> boids: array<Boid>;
 @group(0) @binding(1) var<storage, read_write> bp: BoidParams;
 @group(0) @binding(2) var<storage, read_write> predators: array<Boid>;
 @group(0) @binding(4) var<uniform> tu: TimeUniform;
 @group(0) @binding(7) var<storage, read_write> captured: array<f32>;
+// Current levels in the first NUM_BOIDS entries, spread_alarm writes the next ones after them
+@group(0) @binding(8) var<storage, read_write> alarm: array<f32>;
+@group(0) @binding(10) var<storage, read_write> forage_state: array<ForageState>;
+@group(0) @binding(12) var<storage, read_write> leader: LeaderControl;
+@group(0) @binding(13) var<storage, read_write> wander: array<WanderState>;
+@group(0) @binding(16) var<storage, read_write> neighbours: array<u32>;
 
-fn seperation(boid: Boid) -> vec2<f32> {
+fn seperation(boid: Boid, alarm_level: f32) -> vec2<f32> {
   var ib = boid;
   var close_dx = 0.0;
   var close_dy = 0.0;
   var dv = vec2(0.0);
+  // Alarmed boids keep more space between themselves
+  let protected_range = bp.protected_range * (1.0 + alarm_level*bp.alarm_separation_boost);
 
   for (var i: u32 = 0u; i < NUM_BOIDS; i++) {
     let neighbour_dist: f32 = distance(ib.pos, boids[i].pos);
 
-    if (neighbour_dist < bp.protected_range) {
+    if (neighbour_dist < protected_range) {
       close_dx += (ib.pos.x - boids[i].pos.x)*captured[i];
       close_dy += (ib.pos.y - boids[i].pos.y)*captured[i];
     }
@@ -69,16 +56,66 @@ fn avoid_predators(boid: Boid) -> vec2<f32> {
   return dv;
 }
 
-fn alignment(boid: Boid) -> vec2<f32> {
+// Reads only the current levels, so every boid sees its neighbours as they were last step
+fn update_alarm(bid: u32, boid: Boid) -> f32 {
+  // Seeing a predator fully alarms a boid
+  for (var i: u32 = 0u; i < NUM_PREDATORS; i++) {
+    if (distance(boid.pos, predators[i].pos) < bp.visual_range) {
+      return 1.0;
+    }
+  }
+
+  // Otherwise pick up alarm from the most alarmed neighbour
+  var neighbour_alarm = 0.0;
+
+  for (var i: u32 = 0u; i < NUM_BOIDS; i++) {
+    if (i != bid && distance(boids[i].pos, boid.pos) < bp.visual_range) {
+      neighbour_alarm = max(neighbour_alarm, alarm[i]*captured[i]);
+    }
+  }
+
+  // Each step closes 1 / alarm_delay of the gap to the neighbours level, then everything decays.
+  // Larger values make a wave spread more slowly rather than start later
+  var level = alarm[bid];
+  let spread_level = neighbour_alarm * bp.alarm_spread;
+
+  if (spread_level > level) {
+    level += (spread_level - level) / max(bp.alarm_delay, 1.0);
+  }
+
+  return level * bp.alarm_decay;
+}
+
+// The leader counts as influence neighbours rather than one
+fn neighbour_weight(i: u32) -> f32 {
+  if (i == LEADER_ID && leader.mode != LEADER_OFF) {
+    return leader.influence*captured[i];
+  }
+  return captured[i];
+}
+
+struct Alignment {
+  dv: vec2<f32>,
+  // Living boids within visual range other than this one, only used to colour the boids
+  neighbours: u32,
+}
+
+fn alignment(bid: u32, boid: Boid) -> Alignment {
   var vx_avg = 0.0;
   var vy_avg = 0.0;
   var num_neighbours = 0.0;
+  var count = 0u;
 
   for (var i: u32 = 0u; i < NUM_BOIDS; i++) {
     if (distance(boids[i].pos, boid.pos) < bp.visual_range) {
-      vx_avg += boids[i].vel.x*captured[i];
-      vy_avg += boids[i].vel.y*captured[i];
-      num_neighbours += 1.0*captured[i];
+      let w = neighbour_weight(i);
+      vx_avg += boids[i].vel.x*w;
+      vy_avg += boids[i].vel.y*w;
+      num_neighbours += w;
+
+      if (i != bid && captured[i] != 0.0) {
+        count++;
+      }
     }
   }
 
@@ -88,7 +125,7 @@ fn alignment(boid: Boid) -> vec2<f32> {
   let dvx = (vx_avg - boid.vel.x)*bp.matching_factor;
   let dvy = (vy_avg - boid.vel.y)*bp.matching_factor;
 
-  return vec2<f32>(dvx, dvy);
+  return Alignment(vec2<f32>(dvx, dvy), count);
 }
 
 fn cohesion(boid: Boid) -> vec2<f32> {
@@ -97,9 +134,10 @@ fn cohesion(boid: Boid) -> vec2<f32> {
   var num_neighbours = 0.0;
 
   for (var i: u32 = 0u; i < NUM_BOIDS; i++) {
-    x_avg += boids[i].pos.x*captured[i];
-    y_avg += boids[i].pos.y*captured[i];
-    num_neighbours += 1.0*captured[i];
+    let w = neighbour_weight(i);
+    x_avg += boids[i].pos.x*w;
+    y_avg += boids[i].pos.y*w;
+    num_neighbours += w;
   }
 
   x_avg = x_avg / num_neighbours;
@@ -130,20 +168,95 @@ fn respect_screen_edges(boid: Boid) -> vec2<f32> {
   return dv;
 }
 
-fn respect_speed_limit(boid: Boid) -> vec2<f32> {
-  return clamp(boid.vel, vec2(bp.min_velocity), vec2(bp.max_velocity));
+fn respect_speed_limit(boid: Boid, alarm_level: f32) -> vec2<f32> {
+  let boost = 1.0 + alarm_level*bp.alarm_speed_boost;
+
+  // Old behaviour, diagonal motion is faster and boids can still stall
+  if (bp.limit_mode == LIMIT_COMPONENT) {
+    return clamp(boid.vel, vec2(bp.min_velocity*boost), vec2(bp.max_velocity*boost));
+  }
+
+  let speed = length(boid.vel);
+
+  // A stalled boid has no heading to keep, so leave it to be pushed off by the rules
+  if (speed == 0.0) {
+    return boid.vel;
+  }
+
+  return boid.vel * (clamp(speed, bp.min_speed*boost, bp.max_speed*boost) / speed);
+}
+
+// Exponentially correlated noise, the force forgets itself over wander_correlation steps
+// while its spread stays at wander_amplitude
+fn wander_force(bid: u32) -> vec2<f32> {
+  let rx: RandomResult = hybrid_taus(wander[bid].rng_state);
+  let ry: RandomResult = hybrid_taus(rx.state);
+  wander[bid].rng_state = ry.state;
+
+  let keep = exp(-1.0 / max(bp.wander_correlation, 1.0));
+  let kick = vec2<f32>(rx.value, ry.value) * bp.wander_amplitude * sqrt(1.0 - keep*keep);
+  wander[bid].force = wander[bid].force*keep + kick;
+
+  return wander[bid].force;
+}
+
+fn steer_leader(boid: Boid) -> vec2<f32> {
+  var heading = leader.target_pos;
+
+  if (leader.mode == LEADER_SEEK) {
+    heading = leader.target_pos - boid.pos;
+  }
+
+  if (length(heading) == 0.0) {
+    return vec2(0.0);
+  }
+
+  let desired = normalize(heading) * bp.max_speed;
+  return (desired - boid.vel) * LEADER_RESPONSE;
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn spread_alarm(@builtin(global_invocation_id) id: vec3<u32>) {
+  if (id.x >= NUM_BOIDS) {
+    return;
+  }
+
+  alarm[NUM_BOIDS + id.x] = update_alarm(id.x, boids[id.x]);
 }
 
 @compute 
-@workgroup_size(16, 16, 1) 
+@workgroup_size(64, 1, 1) 
 fn sac(@builtin(global_invocation_id) id: vec3<u32>) {
-  boids[id.x].vel += seperation(boids[id.x]);
-  boids[id.x].vel += avoid_predators(boids[id.x]);
-  boids[id.x].vel += alignment(boids[id.x]);
-  boids[id.x].vel += cohesion(boids[id.x]);
+  // One invocation per boid, so every boid is stepped exactly once per update
+  if (id.x >= NUM_BOIDS) {
+    return;
+  }
+
+  // Only this boid's entries are touched, spread_alarm has already read every current level
+  let alarm_level = alarm[NUM_BOIDS + id.x];
+  alarm[id.x] = alarm_level;
+  // The leader skips the flocking rules but still gets its neighbours counted
+  let aligned = alignment(id.x, boids[id.x]);
+  neighbours[id.x] = aligned.neighbours;
+
+  var dv = vec2(0.0);
+
+  // The leader only follows its controls, everyone else flocks
+  if (id.x == LEADER_ID && leader.mode != LEADER_OFF) {
+    dv += steer_leader(boids[id.x]);
+  } else {
+    dv += seperation(boids[id.x], alarm_level);
+    dv += avoid_predators(boids[id.x]);
+    dv += aligned.dv;
+    dv += cohesion(boids[id.x]);
+    dv += wander_force(id.x);
+    dv += forage_state[id.x].push;
+  }
+  dv += respect_screen_edges(boids[id.x]);
 
-  boids[id.x].vel += respect_screen_edges(boids[id.x]);
-  boids[id.x].vel = respect_speed_limit(boids[id.x]);
+  boids[id.x].vel += limit_force(dv, bp.max_force, bp.limit_mode);
+  boids[id.x].vel = respect_speed_limit(boids[id.x], alarm_level);
   
   boids[id.x].pos += boids[id.x].vel;
 }
@@ -1,150 +1,322 @@
+#include "shared"
+#include "common/view.wgsl"
+#include "common/glyphs.wgsl"
+#include "common/density.wgsl"
+
 // CONSTANTS
 const PI: f32 = 3.14159265;
-const NUM_BOIDS: u32 = 255u;
-const NUM_PREDATORS: u32 = 4u;
-
-// STRUCTS
-struct TimeUniform {
-    time: f32,
-};
-struct ResolutionUniform {
-  xy: vec2<f32>,
-}
-struct ViewParameters {
-  x_shift: f32,
-  y_shift: f32,
-  zoom: f32,
-  time_modifier: f32,
-}
-struct Boid {
-  pos: vec2<f32>,
-  vel: vec2<f32>,
-}
+const ALARM_COLOR: vec3<f32> = vec3<f32>(1.0, 0.55, 0.1);
+const FOOD_COLOR: vec3<f32> = vec3<f32>(0.15, 0.6, 0.2);
+const LEADER_COLOR: vec3<f32> = vec3<f32>(0.2, 0.7, 1.0);
+const INTEREST_COLOR: vec3<f32> = vec3<f32>(1.0, 0.75, 0.2);
+const PROTECTED_COLOR: vec3<f32> = vec3<f32>(1.0, 0.25, 0.25);
+const PURSUIT_COLOR: vec3<f32> = vec3<f32>(1.0, 1.0, 0.4);
+const FLOCK_CENTER_COLOR: vec3<f32> = vec3<f32>(0.3, 1.0, 0.4);
+const LEAD_COLOR: vec3<f32> = vec3<f32>(0.3, 0.9, 1.0);
+const OUTERMOST_COLOR: vec3<f32> = vec3<f32>(1.0, 0.35, 0.9);
+const VISUAL_COLOR: vec3<f32> = vec3<f32>(0.6, 0.7, 1.0);
+const GRID_COLOR: vec3<f32> = vec3<f32>(0.45, 0.5, 0.6);
+const X_AXIS_COLOR: vec3<f32> = vec3<f32>(0.85, 0.35, 0.35);
+const Y_AXIS_COLOR: vec3<f32> = vec3<f32>(0.35, 0.8, 0.4);
+const BOUNDARY_COLOR: vec3<f32> = vec3<f32>(0.6, 0.6, 0.6);
+const SELECTED_COLOR: vec3<f32> = vec3<f32>(1.0, 1.0, 1.0);
 
 // GROUPS AND BINDINGS
 @group(0) @binding(0)
 var<storage, read> boids: array<Boid>;
+@group(0) @binding(1)
+var<storage, read> bp: BoidParams;
 @group(0) @binding(2)
 var<storage, read> predators: array<Boid>;
+@group(0) @binding(3)
+var<storage, read> pp: PredatorParams;
+@group(0) @binding(5)
+var<storage, read> pursuits: array<u32>;
 @group(0) @binding(6)
 var<storage, read> captures: array<u32>;
 @group(0) @binding(7)
 var<storage, read> captured: array<f32>;
+@group(0) @binding(8)
+var<storage, read> alarm: array<f32>;
+@group(0) @binding(9)
+var<storage, read> food: array<FoodPatch>;
+@group(0) @binding(11)
+var<storage, read> fp: FoodParams;
+@group(0) @binding(12)
+var<storage, read> leader: LeaderControl;
+@group(0) @binding(16)
+var<storage, read> neighbours: array<u32>;
+@group(0) @binding(17)
+var<uniform> dp: DensityParams;
+@group(0) @binding(18)
+var<storage, read> density: array<vec2<f32>>;
+@group(0) @binding(19)
+var<storage, read> strategy: StrategyTargets;
 
-@group(1) @binding(0)
-var<uniform> tu: TimeUniform;
-@group(1) @binding(1)
-var<uniform> ru: ResolutionUniform;
-
-@group(2) @binding(0)
-var<storage, read_write> pa: ViewParameters;
+@group(2) @binding(2)
+var<uniform> cp: ColorParams;
+@group(2) @binding(3)
+var<uniform> ov: OverlayParams;
+@group(2) @binding(4)
+var<uniform> grid: GridParams;
 
-// ASPECT RATIO
-fn scale_aspect(fc: vec2<f32>) -> vec2<f32> {
-  // Scale from 0.0 --> 1.0 to -1.0 --> 1.0 
-  var uv: vec2<f32> = ((fc * 2.0) - screen) / max(screen.x, screen.y);
-  uv.y *= -1.0;
-  return uv;
+// COLORS
+fn cosine_palette(t: f32, a: vec3<f32>, b: vec3<f32>, c: vec3<f32>, d: vec3<f32>) -> vec3<f32> {
+  return a + b * cos(PI * 2.0 * (c * t + d));
 }
 
-// COLORS
 fn palette(t: f32) -> vec3<f32> {
   let a: vec3<f32> = vec3<f32>(0.120, 0.618, 0.624); 
   let b: vec3<f32> = vec3<f32>(0.878, 0.214, 0.229);
   let c: vec3<f32> = vec3<f32>(0.654, 0.772, 0.426);
   let d: vec3<f32> = vec3<f32>(0.937, 0.190, 0.152);
 
-  return a * b * cos(PI * 2.0 * (c * t + d));
+  return cosine_palette(t, vec3(0.0), a * b, c, d);
 }
 
-// HASHING
-fn shash21(pos: vec2<f32>) -> f32 {
-  return fract(sin(dot(pos, vec2(12.34777, 67.8913375))) * 4277123.455) * 2.0 - 1.0;
+// The colour map picked in the ui, same coefficients as the legend
+fn color_map(t: f32) -> vec3<f32> {
+  return cosine_palette(t, cp.offset.rgb, cp.amplitude.rgb, cp.frequency.rgb, cp.phase.rgb);
 }
 
-fn shash22(pos: vec2<f32>) -> vec2<f32> {
-  return vec2<f32>(
-    fract(sin(dot(pos, vec2(12.34777, 67.8913375))) * 4277123.455),
-    fract(cos(dot(pos, vec2(71.43119, 33.7654637))) * 9854756.117)
-  ) * 2.0 - 1.0;
+fn nearest_predator_dist(boid: Boid) -> f32 {
+  var closest = 99999999.0;
+
+  for (var i: u32 = 0u; i < NUM_PREDATORS; i++) {
+    closest = min(closest, distance(boid.pos, predators[i].pos));
+  }
+
+  return closest;
+}
+
+fn is_pursued(bid: u32) -> bool {
+  for (var i: u32 = 0u; i < NUM_PREDATORS; i++) {
+    if (pursuits[i] == bid) {
+      return true;
+    }
+  }
+
+  return false;
 }
 
+fn density_at(cell: vec2<i32>) -> f32 {
+  let c = clamp(cell, vec2<i32>(0), vec2<i32>(i32(DENSITY_GRID_X) - 1, i32(DENSITY_GRID_Y) - 1));
+  let d = density[u32(c.y) * DENSITY_GRID_X + u32(c.x)];
+  return select(d.x, d.y, dp.show_average != 0u);
+}
 
-fn rotate(v: vec2<f32>, a: f32, tc: vec2<f32>) -> vec2<f32> {
-    let ca = cos(a);
-    let sa = sin(a);
-    let dv = v - tc;
-    let M = mat2x2(ca, -sa, sa, ca);
+// Bilinear lookup into the density grid, normalised by max_density
+fn sample_density(pos: vec2<f32>) -> f32 {
+  let g = (pos - DENSITY_MIN) / density_cell_size() - 0.5;
+  let base = vec2<i32>(floor(g));
+  let f = fract(g);
 
-    let rotated = dv*M;
-    return rotated;
+  let top = mix(density_at(base), density_at(base + vec2(1, 0)), f.x);
+  let bottom = mix(density_at(base + vec2(0, 1)), density_at(base + vec2(1, 1)), f.x);
+  return clamp(mix(top, bottom, f.y) / max(dp.max_density, 0.0001), 0.0, 1.0);
 }
 
-fn barycentric_triangle(uv: vec2<f32>, boid: Boid, clr: u32, p_size: u32) -> vec3<f32> {
-    let tc = boid.pos; // Center of the triangle
-    let a = 1.5+(0.07*f32(1u + p_size)); // Half-width of the triangle
-    let b = 3.0+(0.07*f32(1u + p_size)); // Half-height of the triangle
+// The quantity ColorParams.mode colours boids by
+fn color_value(bid: u32) -> f32 {
+  let boid = boids[bid];
 
-    let v0 = vec2<f32>(tc.x, tc.y);
-    let v1 = vec2<f32>(tc.x + a, tc.y + b);
-    let v2 = vec2<f32>(tc.x - a, tc.y + b);
+  if (cp.mode == COLOR_SPEED) {
+    return length(boid.vel);
+  } else if (cp.mode == COLOR_HEADING) {
+    let angle = degrees(atan2(boid.vel.y, boid.vel.x));
+    return select(angle, angle + 360.0, angle < 0.0);
+  } else if (cp.mode == COLOR_NEIGHBOURS) {
+    return f32(neighbours[bid]);
+  } else if (cp.mode == COLOR_PREDATOR_DISTANCE) {
+    return nearest_predator_dist(boid);
+  } else if (cp.mode == COLOR_PURSUIT_STATUS) {
+    return select(0.0, 1.0, is_pursued(bid));
+  }
 
-    // Calculate barycentric coordinates
-    let p = uv;
-    var rv0 = v1 - v0;
-    var rv1 = v2 - v0;
-    var rv2 = p - v0;
+  return 0.0;
+}
 
-    //// Calculate the angle of the velocity vector
-    //let angle = atan2(boid.vel.x, boid.vel.y);
+fn boid_color(bid: u32) -> vec3<f32> {
+  if (bid == LEADER_ID && leader.mode != LEADER_OFF) {
+    return LEADER_COLOR;
+  }
 
-    //// Rotate the triangle vertices based on the angle
-    //rv0 = rotate(rv0, angle, tc);
-    //rv1 = rotate(rv1, angle, tc);
-    //rv2 = rotate(rv2, angle, tc);
+  // Alarmed boids fade from white towards ALARM_COLOR
+  if (cp.mode == COLOR_FLAT) {
+    return mix(vec3(1.0), ALARM_COLOR, clamp(alarm[bid], 0.0, 1.0));
+  }
 
-    let d00 = dot(rv0, rv0);
-    let d01 = dot(rv0, rv1);
-    let d11 = dot(rv1, rv1);
-    let d20 = dot(rv2, rv0);
-    let d21 = dot(rv2, rv1);
-    let inv_denom = 1.0 / (d00 * d11 - d01 * d01);
-    let v = (d11 * d20 - d01 * d21) * inv_denom;
-    let w = (d00 * d21 - d01 * d20) * inv_denom;
-    let u = 1.0 - v - w;
+  let range = max(cp.max_value - cp.min_value, 0.00001);
+  return color_map(clamp((color_value(bid) - cp.min_value) / range, 0.0, 1.0));
+}
 
-    // Determine if the point is inside the triangle
-    if (u >= 0.0) && (v >= 0.0) && (w >= 0.0) {
-        return palette(f32(clr) + 12.0)*4.5; // Red color if inside the triangle
-    } else {
-        return vec3<f32>(0.0, 0.0, 0.0); // Black color if outside the triangle
+// DEBUG OVERLAYS
+fn ring(p: vec2<f32>, center: vec2<f32>, radius: f32, width: f32) -> f32 {
+  return 1.0 - smoothstep(0.0, width, abs(distance(p, center) - radius));
+}
+
+fn line(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>, width: f32) -> f32 {
+  return 1.0 - smoothstep(0.0, width, dist_to_segment(p, a, b));
+}
+
+// Paints each enabled overlay over color, later overlays on top
+fn debug_overlay(p: vec2<f32>, color: vec3<f32>) -> vec3<f32> {
+  let w = ov.line_width * pixel_size();
+  let marker = 2.5 * glyph_scale(gp.boid_size);
+  var c = color;
+
+  if (ov.predator_ranges != 0u) {
+    for (var i: u32 = 0u; i < NUM_PREDATORS; i++) {
+      c = mix(c, INTEREST_COLOR, ring(p, predators[i].pos, pp.interest_range, w));
+      c = mix(c, PROTECTED_COLOR, ring(p, predators[i].pos, pp.protected_range, w));
     }
+  }
+
+  if (ov.pursuit_lines != 0u) {
+    for (var i: u32 = 0u; i < NUM_PREDATORS; i++) {
+      if (pursuits[i] != 0xFFFFFFFFu) {
+        c = mix(c, PURSUIT_COLOR, line(p, predators[i].pos, boids[pursuits[i]].pos, w));
+      }
+    }
+  }
+
+  if (ov.strategy_targets != 0u) {
+    let fc = strategy.flock_center;
+    let cross = max(
+      line(p, fc - vec2(marker, 0.0), fc + vec2(marker, 0.0), w),
+      line(p, fc - vec2(0.0, marker), fc + vec2(0.0, marker), w)
+    );
+    c = mix(c, FLOCK_CENTER_COLOR, cross);
+    c = mix(c, LEAD_COLOR, ring(p, boids[strategy.lead_id].pos, marker, w));
+    c = mix(c, OUTERMOST_COLOR, ring(p, boids[strategy.outermost_id].pos, marker, w));
+  }
+
+  if (ov.boid_ranges != 0u) {
+    let boid = boids[min(ov.boid_id, NUM_BOIDS - 1u)];
+    c = mix(c, VISUAL_COLOR, ring(p, boid.pos, bp.visual_range, w));
+    c = mix(c, PROTECTED_COLOR, ring(p, boid.pos, bp.protected_range, w));
+  }
+
+  // The picked agent, in a ring a little wider than its glyph. While comparing, the other pane
+  // rings the agent with the same id, which started from the same seeded state
+  if (ov.selection == SELECT_BOID) {
+    let boid = boids[min(ov.selected_id, NUM_BOIDS - 1u)];
+    c = mix(c, SELECTED_COLOR, ring(p, boid.pos, marker, 1.5 * w));
+  } else if (ov.selection == SELECT_PREDATOR) {
+    let id = min(ov.selected_id, NUM_PREDATORS - 1u);
+    let size = 2.5 * glyph_scale(gp.predator_size) * (1.0 + 0.025 * f32(captures[id]));
+    c = mix(c, SELECTED_COLOR, ring(p, predators[id].pos, size, 1.5 * w));
+  }
+
+  return c;
+}
+
+// WORLD GUIDES
+// 1 on a line of the grid with the given spacing, half a point either side
+fn grid_lines(p: vec2<f32>, spacing: f32, width: f32) -> f32 {
+  let d = abs(fract(p / spacing + 0.5) - 0.5) * spacing;
+  return 1.0 - smoothstep(0.0, width, min(d.x, d.y));
+}
+
+// Grid, axes and boundary, drawn under everything else
+fn world_guides(p: vec2<f32>, color: vec3<f32>) -> vec3<f32> {
+  let w = pixel_size();
+  var c = color;
+
+  if (grid.grid != 0u) {
+    let spacing = grid_spacing(grid.min_spacing);
+    c = mix(c, GRID_COLOR, grid.opacity * grid_lines(p, spacing, 0.5 * w));
+    // Every fifth line a little stronger so the spacing is easier to count
+    c = mix(c, GRID_COLOR, grid.opacity * grid_lines(p, 5.0 * spacing, w));
+  }
+
+  if (grid.axes != 0u) {
+    c = mix(c, X_AXIS_COLOR, 1.0 - smoothstep(0.0, w, abs(p.y)));
+    c = mix(c, Y_AXIS_COLOR, 1.0 - smoothstep(0.0, w, abs(p.x)));
+  }
+
+  if (grid.boundary != 0u) {
+    var corners = array<vec2<f32>, 4>(
+      vec2<f32>(MIN_SCREEN_X, MIN_SCREEN_Y),
+      vec2<f32>(MAX_SCREEN_X, MIN_SCREEN_Y),
+      vec2<f32>(MAX_SCREEN_X, MAX_SCREEN_Y),
+      vec2<f32>(MIN_SCREEN_X, MAX_SCREEN_Y)
+    );
+    var edge = 0.0;
+    for (var i = 0; i < 4; i++) {
+      edge = max(edge, line(p, corners[i], corners[(i + 1) % 4], w));
+    }
+    c = mix(c, BOUNDARY_COLOR, edge);
+  }
+
+  return c;
+}
+
+// HASHING
+fn shash21(pos: vec2<f32>) -> f32 {
+  return fract(sin(dot(pos, vec2(12.34777, 67.8913375))) * 4277123.455) * 2.0 - 1.0;
+}
+
+fn shash22(pos: vec2<f32>) -> vec2<f32> {
+  return vec2<f32>(
+    fract(sin(dot(pos, vec2(12.34777, 67.8913375))) * 4277123.455),
+    fract(cos(dot(pos, vec2(71.43119, 33.7654637))) * 9854756.117)
+  ) * 2.0 - 1.0;
 }
 
-const screen: vec2<f32> = vec2(1366.4, 768.0);
 @fragment
 fn main(@builtin(position) FragCoord: vec4<f32>) -> @location(0) vec4<f32> {
   let t: f32 = tu.time * pa.time_modifier;
   let ts: f32 = sin(t);
-  var uv: vec2<f32> = scale_aspect(FragCoord.xy); // Scale to -1.0 -> 1.0 + fix aspect ratio
-  uv.x += pa.x_shift * pa.zoom;
-  uv.y += pa.y_shift * pa.zoom;
-  uv /= pa.zoom;
+  var uv: vec2<f32> = world_pos(FragCoord.xy);
   var uv0 = uv;
   var color = vec3(0.0);
 // -----------------------------------------------------------------------------------------------
+  color = world_guides(uv, color);
+
+
+  // Food patches dim as they are eaten and brighten as they regrow
+  for (var i: u32 = 0u; i < NUM_FOOD_PATCHES; i++) {
+    let fd = distance(uv, food[i].pos);
+    let fill = food[i].amount / max(fp.max_food, 0.0001);
+    color += FOOD_COLOR * fill * (1.0 - smoothstep(food[i].radius - 1.0, food[i].radius, fd));
+  }
+
+  // Density only mode draws the heatmap in place of the agents
+  if (dp.mode == DENSITY_ONLY) {
+    let d = sample_density(uv);
+    return vec4<f32>(color + color_map(d) * smoothstep(0.0, 0.02, d), 1.0);
+  }
+
+  // Trails sit under the agents, r holds the boid trail and g the predator trail
+  if (tp.enabled != 0u) {
+    let trail = trail_sample(FragCoord.xy);
+    color += tp.boid_color.rgb * trail.r + tp.predator_color.rgb * trail.g;
+  }
 
   
   for (var i: u32 = 0u; i < NUM_BOIDS; i++) {
-    let bd = distance(uv, boids[i].pos);
     // If boid caught, captured[i] == 0.0 and boid won't be visible
-    color += captured[i] - smoothstep(0.0, 1.0, bd)*captured[i];
+    let glow = glyph(uv, boids[i], glyph_scale(gp.boid_size), gp.boid_shape) * captured[i];
+    if (glow > 0.0) {
+      color += boid_color(i)*glow;
+    }
   }
   
   for (var i: u32 = 0u; i < NUM_PREDATORS; i++) {
-    color += barycentric_triangle(uv, predators[i], i, captures[i]);
+    // Predators grow a little with every capture
+    let size = glyph_scale(gp.predator_size) * (1.0 + 0.025 * f32(captures[i]));
+    let fill = glyph(uv, predators[i], size, gp.predator_shape);
+    color += palette(f32(i) + 12.0) * 4.5 * fill;
   }
 
+  if (dp.mode == DENSITY_OVERLAY) {
+    let d = sample_density(uv);
+    color = mix(color, color_map(d), dp.opacity * smoothstep(0.0, 0.02, d));
+  }
+
+  color = debug_overlay(uv, color);
+
 // -----------------------------------------------------------------------------------------------
 
   
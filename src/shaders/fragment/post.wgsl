@@ -0,0 +1,101 @@
+#include "shared"
+
+// Fullscreen passes over the HDR scene, every entry point is its own pipeline on the same layout
+
+// GROUPS AND BINDINGS
+@group(0) @binding(0)
+var src_tex: texture_2d<f32>;
+@group(0) @binding(1)
+var linear_sampler: sampler;
+@group(0) @binding(2)
+var bloom_tex: texture_2d<f32>;
+@group(0) @binding(3)
+var<uniform> post: PostParams;
+
+struct PostVertex {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn fullscreen(@location(0) pos: vec2<f32>) -> PostVertex {
+  var out: PostVertex;
+  out.position = vec4<f32>(pos, 0.0, 1.0);
+  // Clip space y points up, texture v points down
+  out.uv = vec2<f32>(pos.x * 0.5 + 0.5, 0.5 - pos.y * 0.5);
+  return out;
+}
+
+fn luminance(c: vec3<f32>) -> f32 {
+  return dot(c, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// Keeps only the part of each pixel brighter than the threshold, into the half resolution buffer
+@fragment
+fn bright_pass(in: PostVertex) -> @location(0) vec4<f32> {
+  let c = textureSample(src_tex, linear_sampler, in.uv).rgb;
+  let l = luminance(c);
+  let excess = max(l - post.bloom_threshold, 0.0) / max(l, 0.00001);
+  return vec4<f32>(c * excess, 1.0);
+}
+
+// Separable 9 tap gaussian, taps are bloom_radius texels apart
+fn blur(uv: vec2<f32>, dir: vec2<f32>) -> vec4<f32> {
+  var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+  let texel = dir * post.bloom_radius / vec2<f32>(textureDimensions(src_tex));
+
+  var c = textureSample(src_tex, linear_sampler, uv).rgb * weights[0];
+  for (var i = 1; i < 5; i++) {
+    let offset = texel * f32(i);
+    c += textureSample(src_tex, linear_sampler, uv + offset).rgb * weights[i];
+    c += textureSample(src_tex, linear_sampler, uv - offset).rgb * weights[i];
+  }
+
+  return vec4<f32>(c, 1.0);
+}
+
+@fragment
+fn blur_horizontal(in: PostVertex) -> @location(0) vec4<f32> {
+  return blur(in.uv, vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn blur_vertical(in: PostVertex) -> @location(0) vec4<f32> {
+  return blur(in.uv, vec2<f32>(0.0, 1.0));
+}
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+fn aces(x: vec3<f32>) -> vec3<f32> {
+  let a = 2.51;
+  let b = 0.03;
+  let c = 2.43;
+  let d = 0.59;
+  let e = 0.14;
+  return clamp((x * (a * x + b)) / (x * (c * x + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Bloom, exposure, tone mapping and vignette, in that order, into the window or offscreen frame
+@fragment
+fn composite(in: PostVertex) -> @location(0) vec4<f32> {
+  var c = textureSample(src_tex, linear_sampler, in.uv).rgb;
+
+  if (post.bloom != 0u) {
+    c += textureSample(bloom_tex, linear_sampler, in.uv).rgb * post.bloom_intensity;
+  }
+
+  c *= exp2(post.exposure);
+
+  if (post.tone_mapping == TONE_MAP_REINHARD) {
+    c = c / (1.0 + c);
+  } else if (post.tone_mapping == TONE_MAP_ACES) {
+    c = aces(c);
+  }
+
+  if (post.vignette != 0u) {
+    // 0 at the centre, 1 in the corners
+    let d = distance(in.uv, vec2<f32>(0.5)) * 1.41421356;
+    c *= 1.0 - post.vignette_strength * smoothstep(post.vignette_radius, 1.0, d);
+  }
+
+  return vec4<f32>(clamp(c, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);
+}
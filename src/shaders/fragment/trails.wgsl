@@ -0,0 +1,39 @@
+#include "shared"
+#include "common/view.wgsl"
+#include "common/glyphs.wgsl"
+
+// GROUPS AND BINDINGS
+@group(0) @binding(0)
+var<storage, read> boids: array<Boid>;
+@group(0) @binding(2)
+var<storage, read> predators: array<Boid>;
+@group(0) @binding(7)
+var<storage, read> captured: array<f32>;
+
+// Fraction of a trail left after one frame, a trail fades to 1% over its length in frames
+fn decay(trail_length: f32) -> f32 {
+  return pow(0.01, 1.0 / max(trail_length, 1.0));
+}
+
+// Fades the previous frame's trails in trail_tex and stamps the agents on top, boids into r and
+// predators into g so each type keeps its own length and colour
+@fragment
+fn main(@builtin(position) FragCoord: vec4<f32>) -> @location(0) vec4<f32> {
+  let uv = world_pos(FragCoord.xy);
+  let prev = trail_sample(FragCoord.xy);
+
+  var boid_trail = prev.r * decay(tp.boid_length);
+  var predator_trail = prev.g * decay(tp.predator_length);
+
+  for (var i: u32 = 0u; i < NUM_BOIDS; i++) {
+    let fill = glyph(uv, boids[i], glyph_scale(gp.boid_size), gp.boid_shape) * captured[i];
+    boid_trail = max(boid_trail, fill);
+  }
+
+  for (var i: u32 = 0u; i < NUM_PREDATORS; i++) {
+    let fill = glyph(uv, predators[i], glyph_scale(gp.predator_size), gp.predator_shape);
+    predator_trail = max(predator_trail, fill);
+  }
+
+  return vec4<f32>(boid_trail, predator_trail, 0.0, 1.0);
+}
@@ -1,36 +1,66 @@
-use std::error::Error;
-
 use bytemuck::Pod;
-use futures::executor::block_on;
 use nannou::{
     event::{Key, Update},
-    wgpu::{self, Buffer, BufferView},
     App,
 };
 use nannou_egui::egui::{self, epaint::Shadow};
 
-use crate::{update_view_params_buffer, Model, NUM_PREDATORS};
+use crate::{
+    clear_density,
+    comparison::{set_editing, start_comparison, stop_comparison},
+    offscreen::{start_frame_sequence, stop_frame_sequence},
+    recorder::toggle_recording,
+    reset_simulation,
+    svg_export::export_svg,
+    update_boid_params_buffer, update_color_params_buffer, update_density_params_buffer,
+    update_food_params_buffer, update_glyph_params_buffer, update_grid_params_buffer,
+    update_leader_buffer, update_overlay_params_buffer, update_post_params_buffer,
+    update_predator_params_buffer, update_trail_params_buffer, update_view_params_buffer,
+    AgentSnapshot, BoidFormation, ColorMap, ColorMode, ColorParams, ComparisonSide, DensityMode,
+    FollowMode, GlyphShape, GlyphUnits, LeaderMode, Model, PredatorPlacement, RecordSource,
+    SelectionKind, SpeedLimitMode, ToneMapping, VideoFormat, ViewParams, MAX_SCREEN_X,
+    MAX_SCREEN_Y, MIN_SCREEN_X, MIN_SCREEN_Y, NUM_BOIDS, NUM_PREDATORS,
+};
+
+use super::{check_keys, mouse_controls::pane_size};
 
-use super::check_keys;
+// Width of the minimap in points, the height follows the world's aspect ratio
+const MINIMAP_WIDTH: f32 = 300.0;
+// World units shown around the bounds, agents turn back a little past them
+const MINIMAP_MARGIN: f32 = 10.0;
+// Shown in place of the capture buttons, which render Model.sim across the whole frame
+const SPLIT_CAPTURE_NOTE: &str = "Unavailable in split view, it would only show the edited side.";
 
 pub fn update_ui<T>(app: &App, m: &mut Model, u: Update)
 where
     T: Pod + std::fmt::Debug,
 {
     let mut reset = false;
-    let predator_stats: Result<Vec<u32>, Box<dyn Error>>;
-
-    {
-        predator_stats = get_predator_data(app, &m.buffers.cpu_read_predators_captures_buf);
-    }
-
-    let res = match predator_stats {
-        Ok(ps) => ps,
-        Err(error) => {
-            println!("Error fetching predator stats for gui: {error}");
-            vec![0u32; NUM_PREDATORS]
-        }
-    };
+    let mut food_changed = false;
+    let mut limits_changed = false;
+    let mut leader_changed = false;
+    let mut glyphs_changed = false;
+    let mut trails_changed = false;
+    let mut post_changed = false;
+    let mut colors_changed = false;
+    let mut density_changed = false;
+    let mut density_cleared = false;
+    let mut overlay_changed = false;
+    let mut grid_changed = false;
+    let mut sequence_started = false;
+    let mut sequence_stopped = false;
+    let mut recording_toggled = false;
+    let mut view_changed = false;
+    let mut svg_exported = false;
+    let mut comparison_toggled = false;
+    let mut editing_side = None;
+    // Captures and the split view exclude each other, see SPLIT_CAPTURE_NOTE
+    let split_view = m.comparison.split.is_some();
+    let capturing = m.recorder.recording.is_some() || m.frame_sequence.run.is_some();
+    let selection = SelectionKind::ALL
+        .into_iter()
+        .find(|k| *k as u32 == m.variables.overlay_params.selection)
+        .unwrap_or(SelectionKind::None);
 
     {
         let ui = &mut m.ui;
@@ -65,21 +95,1104 @@ where
                         egui::Grid::new("view_params")
                             .spacing(egui::Vec2::new(20.0, 10.0))
                             .show(ui, |ui| {
-                                ui.label("Predator 0:");
-                                ui.label(format!("{}", res[0]));
-                                ui.label("Predator 1:");
-                                ui.label(format!("{}", res[1]));
-                                ui.label("Predator 2:");
-                                ui.label(format!("{}", res[2]));
-                                ui.label("Predator 3:");
-                                ui.label(format!("{}", res[3]));
+                                for (i, captures) in m.agents.captures.iter().enumerate() {
+                                    ui.label(format!("Predator {}:", i));
+                                    ui.label(format!("{}", captures));
+                                }
                             });
                     });
 
-                if ui.button("run").clicked() {
-                    reset = true;
-                }
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(1.0, 0.8, 0.3),
+                            "A/B Comparison:",
+                        );
+
+                        let mut comparing = split_view;
+                        comparison_toggled |= ui
+                            .add_enabled(
+                                !capturing,
+                                egui::Checkbox::new(
+                                    &mut comparing,
+                                    "Split view, same seed and camera",
+                                ),
+                            )
+                            .changed();
+
+                        if capturing {
+                            ui.label("Stop the recording or frame sequence to compare.");
+                        }
+
+                        if comparing {
+                            let mut editing = m.comparison.editing;
+
+                            ui.horizontal(|ui| {
+                                ui.label("Editing:");
+                                for side in ComparisonSide::ALL {
+                                    ui.selectable_value(&mut editing, side, format!("{:?}", side));
+                                }
+                            });
+
+                            if editing != m.comparison.editing {
+                                editing_side = Some(editing);
+                            }
+
+                            ui.label("Controls act on the edited side, reset restarts both.");
+                        }
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.3, 0.8, 0.4),
+                            "Foraging:",
+                        );
+
+                        let fp = &mut m.sim.params.food_params;
+
+                        egui::Grid::new("food_params")
+                            .spacing(egui::Vec2::new(20.0, 10.0))
+                            .show(ui, |ui| {
+                                ui.label("Hunger rate:");
+                                food_changed |= ui
+                                    .add(egui::Slider::new(&mut fp.hunger_rate, 0.0..=0.01))
+                                    .changed();
+                                ui.end_row();
+                                ui.label("Hunger threshold:");
+                                food_changed |= ui
+                                    .add(egui::Slider::new(&mut fp.hunger_threshold, 0.0..=1.0))
+                                    .changed();
+                                ui.end_row();
+                                ui.label("Foraging factor:");
+                                food_changed |= ui
+                                    .add(egui::Slider::new(&mut fp.foraging_factor, 0.0..=0.02))
+                                    .changed();
+                                ui.end_row();
+                                ui.label("Eat rate:");
+                                food_changed |= ui
+                                    .add(egui::Slider::new(&mut fp.eat_rate, 0.0..=0.1))
+                                    .changed();
+                                ui.end_row();
+                                ui.label("Regrow rate:");
+                                food_changed |= ui
+                                    .add(egui::Slider::new(&mut fp.regrow_rate, 0.0..=0.01))
+                                    .changed();
+                                ui.end_row();
+                            });
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.4, 0.6, 1.0),
+                            "Speed Limits:",
+                        );
+
+                        let bp = &mut m.sim.params.boid_params;
+                        let pp = &mut m.sim.params.predator_params;
+                        let mut boids_component = bp.limit_mode == SpeedLimitMode::Component as u32;
+                        let mut predators_component =
+                            pp.limit_mode == SpeedLimitMode::Component as u32;
+
+                        limits_changed |= ui
+                            .checkbox(&mut boids_component, "Boids: per-component clamp")
+                            .changed();
+                        limits_changed |= ui
+                            .checkbox(&mut predators_component, "Predators: per-component clamp")
+                            .changed();
+
+                        bp.limit_mode = if boids_component {
+                            SpeedLimitMode::Component as u32
+                        } else {
+                            SpeedLimitMode::Magnitude as u32
+                        };
+                        pp.limit_mode = if predators_component {
+                            SpeedLimitMode::Component as u32
+                        } else {
+                            SpeedLimitMode::Magnitude as u32
+                        };
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.2, 0.7, 1.0),
+                            "Leader (mode 5):",
+                        );
+
+                        let leader = &mut m.sim.params.leader;
+                        let mut active = leader.mode != LeaderMode::Off as u32;
+
+                        if ui.checkbox(&mut active, "Active").changed() {
+                            leader.mode = if active {
+                                LeaderMode::Heading as u32
+                            } else {
+                                LeaderMode::Off as u32
+                            };
+                            leader_changed = true;
+                        }
+
+                        leader_changed |= ui
+                            .add(
+                                egui::Slider::new(&mut leader.influence, 1.0..=100.0)
+                                    .text("Influence"),
+                            )
+                            .changed();
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.8, 0.5, 0.9),
+                            "Glyphs:",
+                        );
+
+                        let gp = &mut m.variables.glyph_params;
+                        let units = GlyphUnits::ALL
+                            .into_iter()
+                            .find(|u| *u as u32 == gp.units)
+                            .unwrap_or(GlyphUnits::World);
+
+                        for (label, shape) in [
+                            ("Boid shape", &mut gp.boid_shape),
+                            ("Predator shape", &mut gp.predator_shape),
+                        ] {
+                            let current = GlyphShape::ALL
+                                .into_iter()
+                                .find(|s| *s as u32 == *shape)
+                                .unwrap_or(GlyphShape::Arrow);
+
+                            egui::ComboBox::from_label(label)
+                                .selected_text(format!("{:?}", current))
+                                .show_ui(ui, |ui| {
+                                    for s in GlyphShape::ALL {
+                                        glyphs_changed |= ui
+                                            .selectable_value(shape, s as u32, format!("{:?}", s))
+                                            .changed();
+                                    }
+                                });
+                        }
+
+                        egui::ComboBox::from_label("Size units")
+                            .selected_text(format!("{:?}", units))
+                            .show_ui(ui, |ui| {
+                                for u in GlyphUnits::ALL {
+                                    glyphs_changed |= ui
+                                        .selectable_value(
+                                            &mut gp.units,
+                                            u as u32,
+                                            format!("{:?}", u),
+                                        )
+                                        .changed();
+                                }
+                            });
+
+                        // Pixels in screen units, world units otherwise
+                        let max_size = if gp.units == GlyphUnits::Screen as u32 {
+                            40.0
+                        } else {
+                            10.0
+                        };
+
+                        glyphs_changed |= ui
+                            .add(
+                                egui::Slider::new(&mut gp.boid_size, 0.1..=max_size)
+                                    .text("Boid size"),
+                            )
+                            .changed();
+                        glyphs_changed |= ui
+                            .add(
+                                egui::Slider::new(&mut gp.predator_size, 0.1..=max_size)
+                                    .text("Predator size"),
+                            )
+                            .changed();
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.95, 0.95, 0.95),
+                            "Boid Colours:",
+                        );
+
+                        let vars = &mut m.variables;
+                        let params = &m.sim.params;
+                        let mode = ColorMode::ALL
+                            .into_iter()
+                            .find(|c| *c as u32 == vars.color_params.mode)
+                            .unwrap_or(ColorMode::Flat);
+
+                        egui::ComboBox::from_label("Colour by")
+                            .selected_text(format!("{:?}", mode))
+                            .show_ui(ui, |ui| {
+                                for c in ColorMode::ALL {
+                                    if ui
+                                        .selectable_value(
+                                            &mut vars.color_params.mode,
+                                            c as u32,
+                                            format!("{:?}", c),
+                                        )
+                                        .changed()
+                                    {
+                                        let (min, max) = c.default_range(
+                                            &params.boid_params,
+                                            &params.predator_params,
+                                        );
+                                        vars.color_params.min_value = min;
+                                        vars.color_params.max_value = max;
+                                        colors_changed = true;
+                                    }
+                                }
+                            });
+
+                        if mode == ColorMode::Flat {
+                            return;
+                        }
+
+                        egui::ComboBox::from_label("Colour map")
+                            .selected_text(format!("{:?}", vars.color_map))
+                            .show_ui(ui, |ui| {
+                                for c in ColorMap::ALL {
+                                    colors_changed |= ui
+                                        .selectable_value(
+                                            &mut vars.color_map,
+                                            c,
+                                            format!("{:?}", c),
+                                        )
+                                        .changed();
+                                }
+                            });
+
+                        if mode != ColorMode::PursuitStatus {
+                            ui.horizontal(|ui| {
+                                colors_changed |= ui
+                                    .add(
+                                        egui::DragValue::new(&mut vars.color_params.min_value)
+                                            .speed(0.01)
+                                            .prefix("min: "),
+                                    )
+                                    .changed();
+                                colors_changed |= ui
+                                    .add(
+                                        egui::DragValue::new(&mut vars.color_params.max_value)
+                                            .speed(0.01)
+                                            .prefix("max: "),
+                                    )
+                                    .changed();
+                            });
+                        }
+
+                        color_legend(ui, mode, vars.color_map, &vars.color_params);
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(1.0, 0.6, 0.3),
+                            "Density Heatmap (uses the colour map):",
+                        );
+
+                        let dp = &mut m.variables.density_params;
+                        let mode = DensityMode::ALL
+                            .into_iter()
+                            .find(|d| *d as u32 == dp.mode)
+                            .unwrap_or(DensityMode::Off);
+
+                        egui::ComboBox::from_label("Heatmap")
+                            .selected_text(format!("{:?}", mode))
+                            .show_ui(ui, |ui| {
+                                for d in DensityMode::ALL {
+                                    density_changed |= ui
+                                        .selectable_value(
+                                            &mut dp.mode,
+                                            d as u32,
+                                            format!("{:?}", d),
+                                        )
+                                        .changed();
+                                }
+                            });
+
+                        let mut show_average = dp.show_average != 0;
+
+                        if ui
+                            .checkbox(&mut show_average, "Long-term average")
+                            .changed()
+                        {
+                            dp.show_average = show_average as u32;
+                            density_changed = true;
+                        }
+
+                        density_changed |= ui
+                            .add(
+                                egui::Slider::new(&mut dp.kernel_radius, 0.5..=15.0)
+                                    .text("Kernel radius"),
+                            )
+                            .changed();
+                        density_changed |= ui
+                            .add(
+                                egui::Slider::new(&mut dp.average_frames, 10.0..=20000.0)
+                                    .logarithmic(true)
+                                    .text("Average over (steps)"),
+                            )
+                            .changed();
+                        density_changed |= ui
+                            .add(
+                                egui::Slider::new(&mut dp.max_density, 0.1..=30.0)
+                                    .logarithmic(true)
+                                    .text("Max density"),
+                            )
+                            .changed();
+                        density_changed |= ui
+                            .add(egui::Slider::new(&mut dp.opacity, 0.0..=1.0).text("Opacity"))
+                            .changed();
+
+                        if ui.button("clear average").clicked() {
+                            density_cleared = true;
+                        }
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.5, 0.9, 0.8),
+                            "Follow Camera:",
+                        );
+
+                        let follow = &mut m.follow;
+
+                        egui::ComboBox::from_label("Follow")
+                            .selected_text(format!("{:?}", follow.mode))
+                            .show_ui(ui, |ui| {
+                                for mode in FollowMode::ALL {
+                                    ui.selectable_value(
+                                        &mut follow.mode,
+                                        mode,
+                                        format!("{:?}", mode),
+                                    );
+                                }
+                            });
+
+                        match follow.mode {
+                            FollowMode::Predator | FollowMode::PredatorTarget => {
+                                ui.horizontal(|ui| {
+                                    ui.label("Predator:");
+                                    ui.add(
+                                        egui::DragValue::new(&mut follow.predator)
+                                            .clamp_range(0..=NUM_PREDATORS - 1),
+                                    );
+                                });
+                            }
+                            FollowMode::Boid => {
+                                ui.horizontal(|ui| {
+                                    ui.label("Boid:");
+                                    ui.add(
+                                        egui::DragValue::new(&mut follow.boid)
+                                            .clamp_range(0..=NUM_BOIDS - 1),
+                                    );
+                                });
+                            }
+                            FollowMode::Off | FollowMode::Centroid => {}
+                        }
+
+                        ui.add(egui::Slider::new(&mut follow.lag, 0.0..=2.0).text("Lag (s)"));
+                        ui.checkbox(&mut follow.auto_zoom, "Zoom to keep the flock in view");
+                        ui.add_enabled(
+                            follow.auto_zoom,
+                            egui::Slider::new(&mut follow.margin, 0.0..=50.0).text("Margin"),
+                        );
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.6, 0.65, 0.75),
+                            "World Guides:",
+                        );
+
+                        let gp = &mut m.variables.grid_params;
+
+                        ui.horizontal(|ui| {
+                            for (label, flag) in [
+                                ("Grid", &mut gp.grid),
+                                ("Axes", &mut gp.axes),
+                                ("Boundary", &mut gp.boundary),
+                                ("Scale bar", &mut gp.scale_bar),
+                            ] {
+                                let mut on = *flag != 0;
+                                if ui.checkbox(&mut on, label).changed() {
+                                    *flag = on as u32;
+                                    grid_changed = true;
+                                }
+                            }
+                        });
+
+                        ui.add_enabled_ui(gp.grid != 0, |ui| {
+                            grid_changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut gp.min_spacing, 10.0..=200.0)
+                                        .text("Min grid spacing (points)"),
+                                )
+                                .changed();
+                            grid_changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut gp.opacity, 0.05..=1.0)
+                                        .text("Grid opacity"),
+                                )
+                                .changed();
+                        });
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(1.0, 0.75, 0.2),
+                            "Debug Overlays:",
+                        );
+
+                        let op = &mut m.variables.overlay_params;
+
+                        for (label, flag) in [
+                            (
+                                "Predator interest / protected range",
+                                &mut op.predator_ranges,
+                            ),
+                            ("Pursuit lines", &mut op.pursuit_lines),
+                            (
+                                "Flock centre, lead and outermost boid",
+                                &mut op.strategy_targets,
+                            ),
+                            ("Boid visual / protected range", &mut op.boid_ranges),
+                        ] {
+                            let mut enabled = *flag != 0;
+
+                            if ui.checkbox(&mut enabled, label).changed() {
+                                *flag = enabled as u32;
+                                overlay_changed = true;
+                            }
+                        }
+
+                        ui.horizontal(|ui| {
+                            ui.label("Boid:");
+                            overlay_changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut op.boid_id)
+                                        .clamp_range(0..=NUM_BOIDS as u32 - 1),
+                                )
+                                .changed();
+                        });
+
+                        overlay_changed |= ui
+                            .add(
+                                egui::Slider::new(&mut op.line_width, 0.5..=5.0)
+                                    .text("Line width (points)"),
+                            )
+                            .changed();
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.5, 0.6, 0.9),
+                            "Trails:",
+                        );
+
+                        let tp = &mut m.trails.params;
+                        let mut enabled = tp.enabled != 0;
+
+                        if ui.checkbox(&mut enabled, "Enabled").changed() {
+                            tp.enabled = enabled as u32;
+                            trails_changed = true;
+                        }
+
+                        for (label, length, color) in [
+                            ("Boids", &mut tp.boid_length, &mut tp.boid_color),
+                            ("Predators", &mut tp.predator_length, &mut tp.predator_color),
+                        ] {
+                            ui.horizontal(|ui| {
+                                let mut rgb = [color[0], color[1], color[2]];
+
+                                if ui.color_edit_button_rgb(&mut rgb).changed() {
+                                    color[..3].copy_from_slice(&rgb);
+                                    trails_changed = true;
+                                }
+
+                                trails_changed |= ui
+                                    .add(
+                                        egui::Slider::new(length, 1.0..=600.0)
+                                            .text(format!("{label} length (frames)")),
+                                    )
+                                    .changed();
+                            });
+                        }
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(1.0, 0.85, 0.6),
+                            "Post Processing:",
+                        );
+
+                        let pp = &mut m.post.params;
+                        let mut bloom = pp.bloom != 0;
+                        let mut vignette = pp.vignette != 0;
+
+                        if ui.checkbox(&mut bloom, "Bloom").changed() {
+                            pp.bloom = bloom as u32;
+                            post_changed = true;
+                        }
+
+                        ui.add_enabled_ui(bloom, |ui| {
+                            post_changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut pp.bloom_threshold, 0.0..=4.0)
+                                        .text("Threshold"),
+                                )
+                                .changed();
+                            post_changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut pp.bloom_intensity, 0.0..=3.0)
+                                        .text("Intensity"),
+                                )
+                                .changed();
+                            post_changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut pp.bloom_radius, 0.5..=6.0)
+                                        .text("Radius"),
+                                )
+                                .changed();
+                        });
+
+                        let tone_mapping = ToneMapping::ALL
+                            .into_iter()
+                            .find(|t| *t as u32 == pp.tone_mapping)
+                            .unwrap_or(ToneMapping::Off);
+
+                        egui::ComboBox::from_label("Tone mapping")
+                            .selected_text(format!("{:?}", tone_mapping))
+                            .show_ui(ui, |ui| {
+                                for t in ToneMapping::ALL {
+                                    post_changed |= ui
+                                        .selectable_value(
+                                            &mut pp.tone_mapping,
+                                            t as u32,
+                                            format!("{:?}", t),
+                                        )
+                                        .changed();
+                                }
+                            });
+
+                        post_changed |= ui
+                            .add(
+                                egui::Slider::new(&mut pp.exposure, -4.0..=4.0)
+                                    .text("Exposure (stops)"),
+                            )
+                            .changed();
+
+                        if ui.checkbox(&mut vignette, "Vignette").changed() {
+                            pp.vignette = vignette as u32;
+                            post_changed = true;
+                        }
+
+                        ui.add_enabled_ui(vignette, |ui| {
+                            post_changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut pp.vignette_strength, 0.0..=1.0)
+                                        .text("Strength"),
+                                )
+                                .changed();
+                            post_changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut pp.vignette_radius, 0.0..=1.0)
+                                        .text("Radius"),
+                                )
+                                .changed();
+                        });
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.9, 0.8, 0.3),
+                            "Start Formation (applied on reset):",
+                        );
+
+                        let ip = &mut m.variables.init_params;
+                        let boid_formation = BoidFormation::ALL
+                            .into_iter()
+                            .find(|f| *f as u32 == ip.boid_formation)
+                            .unwrap_or(BoidFormation::Rectangle);
+                        let predator_placement = PredatorPlacement::ALL
+                            .into_iter()
+                            .find(|p| *p as u32 == ip.predator_placement)
+                            .unwrap_or(PredatorPlacement::Random);
+
+                        egui::ComboBox::from_label("Boids")
+                            .selected_text(format!("{:?}", boid_formation))
+                            .show_ui(ui, |ui| {
+                                for f in BoidFormation::ALL {
+                                    ui.selectable_value(
+                                        &mut ip.boid_formation,
+                                        f as u32,
+                                        format!("{:?}", f),
+                                    );
+                                }
+                            });
+
+                        egui::ComboBox::from_label("Predators")
+                            .selected_text(format!("{:?}", predator_placement))
+                            .show_ui(ui, |ui| {
+                                for p in PredatorPlacement::ALL {
+                                    ui.selectable_value(
+                                        &mut ip.predator_placement,
+                                        p as u32,
+                                        format!("{:?}", p),
+                                    );
+                                }
+                            });
+
+                        ui.add(egui::Slider::new(&mut ip.spread, 5.0..=100.0).text("Spread"));
+                        ui.add(egui::Slider::new(&mut ip.num_clusters, 1..=8).text("Clusters"));
+
+                        ui.horizontal(|ui| {
+                            ui.label("CSV:");
+                            ui.text_edit_singleline(&mut m.controls.formation_csv);
+                        });
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.8, 0.5, 0.9),
+                            "Frame Sequence:",
+                        );
+
+                        let seq = &mut m.frame_sequence;
+
+                        if let Some(run) = &seq.run {
+                            let first = seq.first_step.min(seq.last_step);
+                            let progress =
+                                (run.step - first + 1) as f32 / (seq.last_step - first + 1) as f32;
+
+                            ui.add(
+                                egui::ProgressBar::new(progress)
+                                    .text(format!("step {} of {}", run.step, seq.last_step)),
+                            );
+
+                            if ui.button("cancel").clicked() {
+                                sequence_stopped = true;
+                            }
+                            return;
+                        }
+
+                        let max_size = app
+                            .window(m.main_wid)
+                            .unwrap()
+                            .device()
+                            .limits()
+                            .max_texture_dimension_2d;
+
+                        ui.horizontal(|ui| {
+                            ui.label("Size:");
+                            ui.add(
+                                egui::DragValue::new(&mut seq.size[0]).clamp_range(1..=max_size),
+                            );
+                            ui.label("x");
+                            ui.add(
+                                egui::DragValue::new(&mut seq.size[1]).clamp_range(1..=max_size),
+                            );
+
+                            for (label, size) in [
+                                ("1080p", [1920, 1080]),
+                                ("4K", [3840, 2160]),
+                                ("8K", [7680, 4320]),
+                            ] {
+                                if ui.button(label).clicked() {
+                                    seq.size = size.map(|s: u32| s.min(max_size));
+                                }
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Steps:");
+                            ui.add(egui::DragValue::new(&mut seq.first_step));
+                            ui.label("to");
+                            ui.add(egui::DragValue::new(&mut seq.last_step));
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Directory:");
+                            ui.text_edit_singleline(&mut seq.output_dir);
+                        });
+
+                        if split_view {
+                            ui.label(SPLIT_CAPTURE_NOTE);
+                        }
+
+                        if ui
+                            .add_enabled(
+                                !split_view,
+                                egui::Button::new("render (resets the simulation)"),
+                            )
+                            .clicked()
+                        {
+                            sequence_started = true;
+                        }
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.4, 0.9, 0.8),
+                            "SVG Export:",
+                        );
+
+                        let se = &mut m.svg_export;
+
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut se.pursuit_lines, "Pursuit lines");
+                            ui.checkbox(&mut se.boundary, "World boundary");
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Directory:");
+                            ui.text_edit_singleline(&mut se.output_dir);
+                        });
+
+                        if split_view {
+                            ui.label(SPLIT_CAPTURE_NOTE);
+                        }
+
+                        if ui
+                            .add_enabled(!split_view, egui::Button::new("export current view"))
+                            .clicked()
+                        {
+                            svg_exported = true;
+                        }
+                    });
+
+                egui::Frame::dark_canvas(ui.style())
+                    .inner_margin(egui::Vec2::new(15.0, 10.0))
+                    .shadow(Shadow::small_dark())
+                    .rounding(10.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            nannou_egui::egui::Rgba::from_rgb(0.9, 0.4, 0.6),
+                            "Video Recorder:",
+                        );
+
+                        let rec = &mut m.recorder;
+
+                        for finishing in &rec.finishing {
+                            ui.label(format!("Encoding {}", finishing.path.display()));
+                        }
+
+                        if let Some(recording) = &rec.recording {
+                            let fps = 60.0 / rec.decimation.max(1) as f32;
+                            ui.label(format!(
+                                "Recording {} frames ({:.1}s) to {}",
+                                recording.frames_kept,
+                                recording.frames_kept as f32 / fps,
+                                recording.path.display()
+                            ));
+
+                            if ui.button("stop (Debug mode: R)").clicked() {
+                                recording_toggled = true;
+                            }
+                            return;
+                        }
+
+                        egui::ComboBox::from_label("Format")
+                            .selected_text(format!("{:?}", rec.format))
+                            .show_ui(ui, |ui| {
+                                for f in VideoFormat::ALL {
+                                    ui.selectable_value(&mut rec.format, f, format!("{:?}", f));
+                                }
+                            });
+
+                        egui::ComboBox::from_label("Source")
+                            .selected_text(format!("{:?}", rec.source))
+                            .show_ui(ui, |ui| {
+                                for s in RecordSource::ALL {
+                                    ui.selectable_value(&mut rec.source, s, format!("{:?}", s));
+                                }
+                            });
+
+                        if rec.source == RecordSource::Offscreen {
+                            let max_size = app
+                                .window(m.main_wid)
+                                .unwrap()
+                                .device()
+                                .limits()
+                                .max_texture_dimension_2d;
+
+                            ui.horizontal(|ui| {
+                                ui.label("Size:");
+                                ui.add(
+                                    egui::DragValue::new(&mut rec.size[0])
+                                        .clamp_range(1..=max_size),
+                                );
+                                ui.label("x");
+                                ui.add(
+                                    egui::DragValue::new(&mut rec.size[1])
+                                        .clamp_range(1..=max_size),
+                                );
+                            });
+                        }
+
+                        ui.add(
+                            egui::Slider::new(&mut rec.decimation, 1..=10)
+                                .text("Keep every nth frame"),
+                        );
+
+                        ui.horizontal(|ui| {
+                            ui.label("Duration limit (s, 0 = none):");
+                            ui.add(
+                                egui::DragValue::new(&mut rec.max_seconds)
+                                    .speed(0.5)
+                                    .clamp_range(0.0..=3600.0),
+                            );
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Directory:");
+                            ui.text_edit_singleline(&mut rec.output_dir);
+                        });
+
+                        if split_view {
+                            ui.label(SPLIT_CAPTURE_NOTE);
+                        }
+
+                        if ui
+                            .add_enabled(!split_view, egui::Button::new("record (Debug mode: R)"))
+                            .clicked()
+                        {
+                            recording_toggled = true;
+                        }
+                    });
+
+                ui.horizontal(|ui| {
+                    let window = app.window(m.main_wid).unwrap();
+                    let mut fullscreen = window.is_fullscreen();
+
+                    if ui.checkbox(&mut fullscreen, "Fullscreen (F11)").changed() {
+                        window.set_fullscreen(fullscreen);
+                    }
+
+                    ui.checkbox(
+                        &mut m.hot_reload.enabled,
+                        "Hot reload shaders (src/shaders)",
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Seed:");
+                    ui.add(egui::DragValue::new(&mut m.variables.seed));
+
+                    if ui.button("new seed").clicked() {
+                        m.variables.seed = nannou::rand::random();
+                        reset = true;
+                    }
+
+                    if ui.button("reset").clicked() {
+                        reset = true;
+                    }
+                });
+            });
+
+        egui::Window::new("Minimap")
+            .frame(egui::Frame {
+                fill: egui::Color32::from_rgb(24, 20, 23),
+                inner_margin: egui::Vec2::new(10.0, 10.0).into(),
+                rounding: 10.0.into(),
+                ..Default::default()
+            })
+            .default_pos(egui::pos2(1000.0, 20.0))
+            .resizable(false)
+            .show(&ctx, |ui| {
+                view_changed |= minimap(
+                    ui,
+                    &mut m.variables.view_params,
+                    pane_size(app, m).to_array(),
+                    &m.agents,
+                );
             });
+
+        if selection != SelectionKind::None {
+            egui::Window::new("Inspector")
+                .frame(egui::Frame {
+                    fill: egui::Color32::from_rgb(24, 20, 23),
+                    inner_margin: egui::Vec2::new(20.0, 10.0).into(),
+                    rounding: 10.0.into(),
+                    ..Default::default()
+                })
+                .default_pos(egui::pos2(1000.0, 320.0))
+                .resizable(false)
+                .show(&ctx, |ui| {
+                    let id = m.variables.overlay_params.selected_id as usize;
+
+                    if inspector(
+                        ui,
+                        selection,
+                        id,
+                        &m.agents,
+                        m.sim.params.predator_params.interest_range,
+                    ) {
+                        m.variables.overlay_params.selection = SelectionKind::None as u32;
+                        overlay_changed = true;
+                    }
+                });
+        }
+
+        if !m.hot_reload.errors.is_empty() {
+            // Stays up until every broken shader builds again, the last good pipelines keep running
+            egui::Window::new("Shader Errors")
+                .frame(egui::Frame {
+                    fill: egui::Color32::from_rgb(40, 14, 16),
+                    inner_margin: egui::Vec2::new(20.0, 10.0).into(),
+                    rounding: 10.0.into(),
+                    ..Default::default()
+                })
+                .default_pos(egui::pos2(600.0, 20.0))
+                .show(&ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for (path, error) in &m.hot_reload.errors {
+                            ui.colored_label(egui::Color32::from_rgb(255, 110, 100), *path);
+                            ui.label(egui::RichText::new(error).monospace());
+                        }
+                    });
+                });
+        }
+    }
+
+    if comparison_toggled {
+        if m.comparison.split.is_some() {
+            stop_comparison(m);
+        } else {
+            start_comparison(app, m);
+        }
+    }
+
+    if let Some(side) = editing_side {
+        set_editing(m, side);
+    }
+
+    if limits_changed {
+        update_boid_params_buffer(app, m);
+        update_predator_params_buffer(app, m);
+    }
+
+    if reset {
+        reset_simulation(app, m);
+    }
+
+    if leader_changed {
+        update_leader_buffer(app, m);
+    }
+
+    if food_changed {
+        update_food_params_buffer(app, m);
+    }
+
+    if glyphs_changed {
+        update_glyph_params_buffer(app, m);
+    }
+
+    if trails_changed {
+        update_trail_params_buffer(app, m);
+    }
+
+    if post_changed {
+        update_post_params_buffer(app, m);
+    }
+
+    if colors_changed {
+        update_color_params_buffer(app, m);
+    }
+
+    if density_changed {
+        update_density_params_buffer(app, m);
+    }
+
+    if density_cleared {
+        clear_density(app, m);
+    }
+
+    if overlay_changed {
+        update_overlay_params_buffer(app, m);
+    }
+
+    if grid_changed {
+        update_grid_params_buffer(app, m);
+    }
+
+    if view_changed {
+        update_view_params_buffer(app, m);
+    }
+
+    if svg_exported {
+        match export_svg(app, m) {
+            Ok(path) => println!("Exported svg to {}", path.display()),
+            Err(error) => println!("Error exporting svg: {error}"),
+        }
+    }
+
+    if sequence_started {
+        start_frame_sequence(app, m, u);
+    }
+
+    if sequence_stopped {
+        stop_frame_sequence(app, m);
+    }
+
+    if recording_toggled {
+        toggle_recording(app, m);
     }
 
     {
@@ -87,40 +1200,227 @@ where
     }
 }
 
-pub(crate) fn get_predator_data<'a, T: Pod + std::fmt::Debug>(
-    app: &'a App,
-    buffer: &'a Buffer,
-) -> Result<Vec<T>, Box<dyn std::error::Error>> {
-    let mw = app.main_window();
-    let device = mw.device();
+// Returns true once the selection should be cleared
+fn inspector(
+    ui: &mut egui::Ui,
+    selection: SelectionKind,
+    id: usize,
+    agents: &AgentSnapshot,
+    interest_range: f32,
+) -> bool {
+    let agent = match selection {
+        SelectionKind::Boid => agents.boids.get(id),
+        SelectionKind::Predator => agents.predators.get(id),
+        SelectionKind::None => None,
+    };
 
-    // Map the buffer for reading
-    let buffer_slice = buffer.slice(..);
-    let (tx, rx) = futures::channel::oneshot::channel();
+    let Some(agent) = agent else {
+        ui.label("Waiting for agent data");
+        return ui.button("Deselect").clicked();
+    };
 
-    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-        tx.send(result).unwrap();
-    });
+    let alive = |boid: usize| agents.captured.get(boid).is_some_and(|c| *c > 0.5);
 
-    // Wait for the GPU to finish executing the commands
-    device.poll(wgpu::Maintain::Wait);
-    // Wait for the buffer to be mapped
-    let result = block_on(rx);
+    egui::Grid::new("inspector")
+        .spacing(egui::Vec2::new(20.0, 10.0))
+        .show(ui, |ui| {
+            ui.label("Agent:");
+            ui.label(format!("{:?} {}", selection, id));
+            ui.end_row();
+            ui.label("Position:");
+            ui.label(format!("({:.2}, {:.2})", agent.pos[0], agent.pos[1]));
+            ui.end_row();
+            ui.label("Velocity:");
+            ui.label(format!("({:.3}, {:.3})", agent.vel[0], agent.vel[1]));
+            ui.end_row();
+            ui.label("Speed:");
+            ui.label(format!("{:.3}", agent.vel[0].hypot(agent.vel[1])));
+            ui.end_row();
 
-    match result {
-        Ok(_) => {
-            let buf_view = buffer_slice.get_mapped_range();
-            let data: &[T] = bytemuck::cast_slice(&buf_view);
+            match selection {
+                SelectionKind::Boid => {
+                    ui.label("Neighbours:");
+                    ui.label(match agents.neighbours.get(id) {
+                        Some(count) => count.to_string(),
+                        None => String::from("-"),
+                    });
+                    ui.end_row();
+                    ui.label("Alive:");
+                    ui.label(if alive(id) { "Yes" } else { "Captured" });
+                    ui.end_row();
+                }
+                SelectionKind::Predator => {
+                    // Predators have no neighbour count of their own, these are the boids they see
+                    let in_range = agents
+                        .boids
+                        .iter()
+                        .enumerate()
+                        .filter(|(boid, b)| {
+                            alive(*boid)
+                                && (b.pos[0] - agent.pos[0]).hypot(b.pos[1] - agent.pos[1])
+                                    < interest_range
+                        })
+                        .count();
+
+                    ui.label("Boids in range:");
+                    ui.label(in_range.to_string());
+                    ui.end_row();
+                    ui.label("Alive:");
+                    ui.label("Yes");
+                    ui.end_row();
+                    ui.label("Pursuing:");
+                    ui.label(
+                        match agents
+                            .pursuits
+                            .get(id)
+                            .filter(|target| agents.boids.get(**target as usize).is_some())
+                        {
+                            Some(target) => format!("Boid {}", target),
+                            None => String::from("Nothing"),
+                        },
+                    );
+                    ui.end_row();
+                    ui.label("Captures:");
+                    ui.label(match agents.captures.get(id) {
+                        Some(count) => count.to_string(),
+                        None => String::from("-"),
+                    });
+                    ui.end_row();
+                }
+                SelectionKind::None => {}
+            }
+        });
 
-            let owned_data = data.to_owned();
-            drop(buf_view);
-            buffer.unmap();
+    ui.button("Deselect").clicked()
+}
 
-            Ok(owned_data)
+// Rust side of cosine_palette in boids_frag.wgsl so the legend matches the boids
+fn cosine_palette(t: f32, color_map: ColorMap) -> egui::Color32 {
+    let [offset, amplitude, frequency, phase] = color_map.coefficients();
+    let channel = |i: usize| {
+        let c = offset[i]
+            + amplitude[i] * (std::f32::consts::TAU * (frequency[i] * t + phase[i])).cos();
+        (c.clamp(0.0, 1.0) * 255.0) as u8
+    };
+
+    egui::Color32::from_rgb(channel(0), channel(1), channel(2))
+}
+
+fn color_legend(ui: &mut egui::Ui, mode: ColorMode, color_map: ColorMap, cp: &ColorParams) {
+    if mode == ColorMode::PursuitStatus {
+        for (t, label) in [(0.0, "Not pursued"), (1.0, "Pursued")] {
+            ui.horizontal(|ui| {
+                let (rect, _) =
+                    ui.allocate_exact_size(egui::Vec2::new(16.0, 16.0), egui::Sense::hover());
+                ui.painter()
+                    .rect_filled(rect, 2.0, cosine_palette(t, color_map));
+                ui.label(label);
+            });
         }
-        Err(e) => {
-            eprintln!("Error retrieving gpu data: {:?}", e);
-            Err(Box::new(e))
+        return;
+    }
+
+    let steps = 64;
+    let (rect, _) = ui.allocate_exact_size(egui::Vec2::new(240.0, 16.0), egui::Sense::hover());
+    let step_width = rect.width() / steps as f32;
+
+    for i in 0..steps {
+        let t = i as f32 / (steps - 1) as f32;
+        let x = rect.left() + i as f32 * step_width;
+        let step = egui::Rect::from_min_size(
+            egui::pos2(x, rect.top()),
+            egui::Vec2::new(step_width + 0.5, rect.height()),
+        );
+        ui.painter()
+            .rect_filled(step, 0.0, cosine_palette(t, color_map));
+    }
+
+    let units = match mode {
+        ColorMode::Heading => "°",
+        ColorMode::Neighbours => " boids",
+        _ => "",
+    };
+
+    ui.horizontal(|ui| {
+        ui.label(format!("{:.2}{units}", cp.min_value));
+        ui.add_space(120.0);
+        ui.label(format!("{:.2}{units}", cp.max_value));
+    });
+}
+
+// The world bounds plus a margin, with a dot per live agent and the view of a pane as a
+// rectangle. Clicking or dragging centres the view on the pointer, returns true when it did.
+fn minimap(ui: &mut egui::Ui, vp: &mut ViewParams, pane: [f32; 2], agents: &AgentSnapshot) -> bool {
+    let world_min = egui::pos2(MIN_SCREEN_X - MINIMAP_MARGIN, MIN_SCREEN_Y - MINIMAP_MARGIN);
+    let world_max = egui::pos2(MAX_SCREEN_X + MINIMAP_MARGIN, MAX_SCREEN_Y + MINIMAP_MARGIN);
+    let world_size = world_max - world_min;
+    let size = egui::Vec2::new(MINIMAP_WIDTH, MINIMAP_WIDTH * world_size.y / world_size.x);
+
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+
+    // World y points up, screen y points down
+    let to_screen = |p: [f32; 2]| {
+        egui::pos2(
+            rect.left() + (p[0] - world_min.x) / world_size.x * rect.width(),
+            rect.bottom() - (p[1] - world_min.y) / world_size.y * rect.height(),
+        )
+    };
+    let to_world = |p: egui::Pos2| {
+        [
+            world_min.x + (p.x - rect.left()) / rect.width() * world_size.x,
+            world_min.y + (rect.bottom() - p.y) / rect.height() * world_size.y,
+        ]
+    };
+
+    painter.rect_filled(rect, 4.0, egui::Color32::from_rgb(10, 10, 14));
+    painter.rect_stroke(
+        egui::Rect::from_two_pos(
+            to_screen([MIN_SCREEN_X, MIN_SCREEN_Y]),
+            to_screen([MAX_SCREEN_X, MAX_SCREEN_Y]),
+        ),
+        0.0,
+        egui::Stroke::new(1.0, egui::Color32::from_gray(90)),
+    );
+
+    // captured_boids_buf holds 1.0 for boids still in play
+    for (boid, _) in agents
+        .boids
+        .iter()
+        .zip(&agents.captured)
+        .filter(|(_, alive)| **alive > 0.5)
+    {
+        painter.circle_filled(to_screen(boid.pos), 1.2, egui::Color32::from_gray(220));
+    }
+
+    for predator in &agents.predators {
+        painter.circle_filled(
+            to_screen(predator.pos),
+            3.0,
+            egui::Color32::from_rgb(255, 80, 60),
+        );
+    }
+
+    // Same extents as scale_aspect in view.wgsl, the longer side of a pane spans 2.0 / zoom
+    let longest = f32::max(pane[0], pane[1]);
+    let half_extent = [pane[0] / longest / vp.zoom, pane[1] / longest / vp.zoom];
+    painter.rect_stroke(
+        egui::Rect::from_two_pos(
+            to_screen([vp.x_shift - half_extent[0], vp.y_shift - half_extent[1]]),
+            to_screen([vp.x_shift + half_extent[0], vp.y_shift + half_extent[1]]),
+        ),
+        0.0,
+        egui::Stroke::new(1.5, egui::Color32::from_rgb(255, 220, 90)),
+    );
+
+    if response.clicked() || response.dragged() {
+        if let Some(pointer) = response.interact_pointer_pos() {
+            let [x, y] = to_world(pointer);
+            vp.x_shift = x;
+            vp.y_shift = y;
+            return true;
         }
     }
+
+    false
 }
@@ -1,10 +1,13 @@
+use super::mouse_controls::screen_to_world;
 use crate::{
-    update_boid_params_buffer, update_boid_props, update_predator_params_buffer,
-    update_view_params_buffer, Boid, KeyboardControlMode, Model,
+    update_boid_params_buffer, update_boid_props, update_leader_buffer,
+    update_predator_params_buffer, update_view_params_buffer, Boid, KeyboardControlMode,
+    LeaderMode, Model, SpeedLimitMode,
 };
 use bytemuck::Pod;
 use futures::executor::block_on;
 use nannou::event::Key;
+use nannou::glam::Vec2;
 use nannou::wgpu::Buffer;
 use nannou::{wgpu, App};
 use std::thread;
@@ -25,6 +28,9 @@ pub fn check_keys(app: &App, model: &mut Model) {
     } else if pressed.contains(&Key::Key4) {
         model.controls.kcm = KeyboardControlMode::View;
         println!("kcm: {:?}", model.controls.kcm);
+    } else if pressed.contains(&Key::Key5) {
+        model.controls.kcm = KeyboardControlMode::Leader;
+        println!("kcm: {:?}", model.controls.kcm);
     }
 
     match model.controls.kcm {
@@ -32,6 +38,7 @@ pub fn check_keys(app: &App, model: &mut Model) {
         KeyboardControlMode::Boids => boids_controls(app, model),
         KeyboardControlMode::Predator => predator_controls(app, model),
         KeyboardControlMode::Debug => debug_controls(app, model),
+        KeyboardControlMode::Leader => leader_controls(app, model),
     }
 }
 
@@ -47,44 +54,89 @@ fn boids_controls(app: &App, model: &mut Model) {
         dval = -1.0f32;
     }
 
+    let component_limits =
+        model.sim.params.boid_params.limit_mode == SpeedLimitMode::Component as u32;
+
     if pressed.contains(&Key::Plus) {
-        let maxv = &mut model.variables.boid_params.max_velocity;
+        let maxv = if component_limits {
+            &mut model.sim.params.boid_params.max_velocity
+        } else {
+            &mut model.sim.params.boid_params.max_speed
+        };
         *maxv = f32::max(0.1, *maxv + (0.003 * dval));
         update_boid_params_buffer(app, model);
     }
     if pressed.contains(&Key::Minus) {
-        let minv = &mut model.variables.boid_params.min_velocity;
-        *minv = f32::max(0.0, *minv + (0.003 * dval));
+        let bp = &mut model.sim.params.boid_params;
+        let (minv, maxv) = if component_limits {
+            (&mut bp.min_velocity, bp.max_velocity)
+        } else {
+            (&mut bp.min_speed, bp.max_speed)
+        };
+        *minv = f32::max(0.0, *minv + (0.003 * dval)).min(maxv);
+        update_boid_params_buffer(app, model);
+    }
+    if pressed.contains(&Key::F) {
+        let mf = &mut model.sim.params.boid_params.max_force;
+        *mf = f32::max(0.0, *mf + (0.0003 * dval));
         update_boid_params_buffer(app, model);
     }
     if pressed.contains(&Key::T) {
-        let tf = &mut model.variables.boid_params.turn_factor;
+        let tf = &mut model.sim.params.boid_params.turn_factor;
         *tf = f32::max(0.0, *tf + (0.003 * dval));
         update_boid_params_buffer(app, model);
     } else if pressed.contains(&Key::V) {
-        let vr = &mut model.variables.boid_params.visual_range;
+        let vr = &mut model.sim.params.boid_params.visual_range;
         *vr = f32::max(0.0, *vr + (0.01 * dval));
         update_boid_params_buffer(app, model);
     } else if pressed.contains(&Key::P) {
-        let pr = &mut model.variables.boid_params.protected_range;
+        let pr = &mut model.sim.params.boid_params.protected_range;
         *pr = f32::max(0.0, *pr + (0.01 * dval));
         update_boid_params_buffer(app, model);
     } else if pressed.contains(&Key::C) {
-        let cf = &mut model.variables.boid_params.centering_factor;
+        let cf = &mut model.sim.params.boid_params.centering_factor;
         *cf = f32::max(0.0, *cf + (0.0000003 * dval));
         update_boid_params_buffer(app, model);
     } else if pressed.contains(&Key::A) {
-        let af = &mut model.variables.boid_params.self_avoid_factor;
+        let af = &mut model.sim.params.boid_params.self_avoid_factor;
         *af = f32::max(0.0, *af + (0.0003 * dval));
         update_boid_params_buffer(app, model);
     } else if pressed.contains(&Key::P) {
-        let af = &mut model.variables.boid_params.predator_avoid_factor;
+        let af = &mut model.sim.params.boid_params.predator_avoid_factor;
         *af = f32::max(0.0, *af + (0.0003 * dval));
         update_boid_params_buffer(app, model);
     } else if pressed.contains(&Key::M) {
-        let mf = &mut model.variables.boid_params.matching_factor;
+        let mf = &mut model.sim.params.boid_params.matching_factor;
         *mf = f32::max(0.0, *mf + (0.0003 * dval));
         update_boid_params_buffer(app, model);
+    } else if pressed.contains(&Key::L) {
+        let als = &mut model.sim.params.boid_params.alarm_spread;
+        *als = f32::clamp(*als + (0.003 * dval), 0.0, 1.0);
+        update_boid_params_buffer(app, model);
+    } else if pressed.contains(&Key::D) {
+        let ald = &mut model.sim.params.boid_params.alarm_delay;
+        *ald = f32::max(1.0, *ald + (0.1 * dval));
+        update_boid_params_buffer(app, model);
+    } else if pressed.contains(&Key::K) {
+        let alk = &mut model.sim.params.boid_params.alarm_decay;
+        *alk = f32::clamp(*alk + (0.001 * dval), 0.0, 1.0);
+        update_boid_params_buffer(app, model);
+    } else if pressed.contains(&Key::S) {
+        let asb = &mut model.sim.params.boid_params.alarm_speed_boost;
+        *asb = f32::max(0.0, *asb + (0.01 * dval));
+        update_boid_params_buffer(app, model);
+    } else if pressed.contains(&Key::E) {
+        let aeb = &mut model.sim.params.boid_params.alarm_separation_boost;
+        *aeb = f32::max(0.0, *aeb + (0.01 * dval));
+        update_boid_params_buffer(app, model);
+    } else if pressed.contains(&Key::W) {
+        let wa = &mut model.sim.params.boid_params.wander_amplitude;
+        *wa = f32::max(0.0, *wa + (0.0001 * dval));
+        update_boid_params_buffer(app, model);
+    } else if pressed.contains(&Key::R) {
+        let wc = &mut model.sim.params.boid_params.wander_correlation;
+        *wc = f32::max(1.0, *wc + (0.5 * dval));
+        update_boid_params_buffer(app, model);
     }
 }
 
@@ -101,41 +153,107 @@ fn predator_controls(app: &App, model: &mut Model) {
         dval = -1.0f32;
     }
 
+    let component_limits =
+        model.sim.params.predator_params.limit_mode == SpeedLimitMode::Component as u32;
+
     if pressed.contains(&Key::Plus) {
-        let maxv = &mut model.variables.predator_params.max_velocity;
+        let maxv = if component_limits {
+            &mut model.sim.params.predator_params.max_velocity
+        } else {
+            &mut model.sim.params.predator_params.max_speed
+        };
         *maxv = f32::max(0.1, *maxv + (0.03 * dval));
         update_predator_params_buffer(app, model);
     } else if pressed.contains(&Key::Minus) {
-        let minv = &mut model.variables.predator_params.min_velocity;
-        *minv = f32::max(0.0, *minv + (0.03 * dval));
+        let pp = &mut model.sim.params.predator_params;
+        let (minv, maxv) = if component_limits {
+            (&mut pp.min_velocity, pp.max_velocity)
+        } else {
+            (&mut pp.min_speed, pp.max_speed)
+        };
+        *minv = f32::max(0.0, *minv + (0.03 * dval)).min(maxv);
+        update_predator_params_buffer(app, model);
+    } else if pressed.contains(&Key::F) {
+        let mf = &mut model.sim.params.predator_params.max_force;
+        *mf = f32::max(0.0, *mf + (0.01 * dval));
         update_predator_params_buffer(app, model);
     } else if pressed.contains(&Key::T) {
-        let tf = &mut model.variables.predator_params.turn_factor;
+        let tf = &mut model.sim.params.predator_params.turn_factor;
         *tf = f32::max(0.0, *tf + (0.003 * dval));
         update_predator_params_buffer(app, model);
     } else if pressed.contains(&Key::V) {
-        let vr = &mut model.variables.predator_params.visual_range;
+        let vr = &mut model.sim.params.predator_params.visual_range;
         *vr = f32::max(0.0, *vr + (0.001 * dval));
         update_predator_params_buffer(app, model);
     } else if pressed.contains(&Key::I) {
-        let vr = &mut model.variables.predator_params.interest_range;
+        let vr = &mut model.sim.params.predator_params.interest_range;
         *vr = f32::max(0.0, *vr + (0.001 * dval));
         update_predator_params_buffer(app, model);
     } else if pressed.contains(&Key::P) {
-        let pf = &mut model.variables.predator_params.pursuit_factor;
+        let pf = &mut model.sim.params.predator_params.pursuit_factor;
         *pf = f32::max(0.0, *pf + (0.001 * dval));
         update_predator_params_buffer(app, model);
     } else if pressed.contains(&Key::O) {
-        let pf = &mut model.variables.predator_params.pursuit_multiplier;
+        let pf = &mut model.sim.params.predator_params.pursuit_multiplier;
         *pf = f32::max(0.0, *pf + (0.05 * dval));
         update_predator_params_buffer(app, model);
     } else if pressed.contains(&Key::M) {
-        let mf = &mut model.variables.predator_params.matching_factor;
+        let mf = &mut model.sim.params.predator_params.matching_factor;
         *mf = f32::max(0.0, *mf + (0.001 * dval));
         update_predator_params_buffer(app, model);
     }
 }
 
+fn leader_controls(app: &App, model: &mut Model) {
+    let pressed = &app.keys.down;
+    let mut heading = Vec2::ZERO;
+    let mut changed = false;
+
+    if pressed.contains(&Key::Left) {
+        heading.x -= 1.0;
+    }
+    if pressed.contains(&Key::Right) {
+        heading.x += 1.0;
+    }
+    if pressed.contains(&Key::Up) {
+        heading.y += 1.0;
+    }
+    if pressed.contains(&Key::Down) {
+        heading.y -= 1.0;
+    }
+
+    // Holding the left mouse button in the main window pulls the leader towards the cursor
+    let seek = app.mouse.buttons.left().is_down() && app.mouse.window == Some(model.main_wid);
+    let seek_pos = screen_to_world(app, model, app.mouse.position());
+
+    let leader = &mut model.sim.params.leader;
+
+    if seek {
+        leader.target_pos = seek_pos.into();
+        leader.mode = LeaderMode::Seek as u32;
+        changed = true;
+    } else if heading != Vec2::ZERO {
+        leader.target_pos = heading.normalize().into();
+        leader.mode = LeaderMode::Heading as u32;
+        changed = true;
+    } else if pressed.contains(&Key::Back) {
+        leader.mode = LeaderMode::Off as u32;
+        changed = true;
+    }
+
+    if pressed.contains(&Key::Plus) {
+        leader.influence += 0.5;
+        changed = true;
+    } else if pressed.contains(&Key::Minus) {
+        leader.influence = f32::max(1.0, leader.influence - 0.5);
+        changed = true;
+    }
+
+    if changed {
+        update_leader_buffer(app, model);
+    }
+}
+
 fn debug_controls(app: &App, model: &mut Model) {
     // PRINT CURRENT FRAME --------------------------------------------------------
     if app.keys.down.contains(&Key::Space) {
@@ -152,27 +270,34 @@ fn debug_controls(app: &App, model: &mut Model) {
         println!("\nview_params:\n{:#?}\n", model.variables.view_params);
         thread::sleep(Duration::from_millis(50));
     } else if app.keys.down.contains(&Key::B) {
-        println!("\nboids_params:\n{:#?}", model.variables.boid_params);
+        println!("\nboids_params:\n{:#?}", model.sim.params.boid_params);
         thread::sleep(Duration::from_millis(50));
     } else if app.keys.down.contains(&Key::V) {
-        println!("\npredator_params:\n{:#?}", model.variables.predator_params);
+        println!(
+            "\npredator_params:\n{:#?}",
+            model.sim.params.predator_params
+        );
     } else if app.keys.down.contains(&Key::Comma) {
-        print_gpu_data::<Boid>(app, &model.buffers.cpu_read_boids_pos_buf, "Boid");
+        print_gpu_data::<Boid>(app, &model.sim.buffers.cpu_read_boids_pos_buf, "Boid");
         thread::sleep(Duration::from_millis(50));
     } else if app.keys.down.contains(&Key::Semicolon) {
-        print_gpu_data::<Boid>(app, &model.buffers.cpu_read_predators_pos_buf, "Predator");
+        print_gpu_data::<Boid>(
+            app,
+            &model.sim.buffers.cpu_read_predators_pos_buf,
+            "Predator",
+        );
         thread::sleep(Duration::from_millis(50));
     } else if app.keys.down.contains(&Key::P) {
         print_gpu_data::<[u32; 4]>(
             app,
-            &model.buffers.cpu_read_predators_pursuits_buf,
+            &model.sim.buffers.cpu_read_predators_pursuits_buf,
             "Pursuit IDs",
         );
         thread::sleep(Duration::from_millis(50));
     } else if app.keys.down.contains(&Key::C) {
         print_gpu_data::<[f32; 4]>(
             app,
-            &model.buffers.cpu_read_predators_captures_buf,
+            &model.sim.buffers.cpu_read_predators_captures_buf,
             "Captures per Predator",
         );
         thread::sleep(Duration::from_millis(50))
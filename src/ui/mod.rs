@@ -1,5 +1,8 @@
 pub mod gui;
 pub mod keyboard_controls;
+pub mod mouse_controls;
+pub mod scale_bar;
 
 pub use gui::update_ui;
 pub use keyboard_controls::check_keys;
+pub use mouse_controls::{mouse_moved, mouse_pressed, mouse_released, mouse_wheel};
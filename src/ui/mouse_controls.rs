@@ -0,0 +1,201 @@
+use nannou::{
+    event::{MouseButton, MouseScrollDelta, TouchPhase},
+    glam::Vec2,
+    App,
+};
+
+use crate::{
+    update_overlay_params_buffer, update_view_params_buffer, ComparisonSide, KeyboardControlMode,
+    Model, SelectionKind, INITIAL_ZOOM,
+};
+
+// Zoom factor of one wheel notch
+const ZOOM_STEP: f32 = 1.1;
+// Touchpads scroll in pixels, this many count as one notch
+const PIXELS_PER_LINE: f32 = 40.0;
+// Longest gap between the clicks of a double click, in seconds
+const DOUBLE_CLICK_TIME: f32 = 0.3;
+// A left click picks an agent if the cursor moved less than this many points before release
+const CLICK_SLOP: f32 = 4.0;
+// Agents further than this many points from the click aren't picked
+const PICK_RADIUS: f32 = 12.0;
+
+// Size in points of each pane, the whole window unless a comparison splits it
+pub(crate) fn pane_size(app: &App, model: &Model) -> Vec2 {
+    let window = app.window(model.main_wid).unwrap();
+    let (w, h) = window.inner_size_points();
+
+    if model.comparison.split.is_some() {
+        Vec2::new(w / 2.0, h)
+    } else {
+        Vec2::new(w, h)
+    }
+}
+
+// Scale and centre of the pane under point
+fn pane_at(app: &App, model: &Model, point: Vec2) -> (f32, Vec2) {
+    let size = pane_size(app, model);
+    let uv_per_point = 2.0 / size.max_element();
+
+    if model.comparison.split.is_none() {
+        return (uv_per_point, Vec2::ZERO);
+    }
+
+    let centre_x = if point.x < 0.0 {
+        -size.x / 2.0
+    } else {
+        size.x / 2.0
+    };
+
+    (uv_per_point, Vec2::new(centre_x, 0.0))
+}
+
+// Inverse of the scale_aspect, shift and zoom steps in boids_frag.wgsl
+pub(crate) fn screen_to_world(app: &App, model: &Model, point: Vec2) -> Vec2 {
+    let vp = &model.variables.view_params;
+    let (uv_per_point, centre) = pane_at(app, model, point);
+
+    // Mouse positions are centred on the window with y pointing up, same as uv
+    let uv = (point - centre) * uv_per_point;
+
+    uv / vp.zoom + Vec2::new(vp.x_shift, vp.y_shift)
+}
+
+// Keeps the world point under the cursor in place
+pub(crate) fn mouse_wheel(app: &App, model: &mut Model, delta: MouseScrollDelta, _: TouchPhase) {
+    let notches = match delta {
+        MouseScrollDelta::LineDelta(_, y) => y,
+        MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+    };
+
+    let cursor = app.mouse.position();
+    let anchor = screen_to_world(app, model, cursor);
+
+    model.variables.view_params.zoom *= ZOOM_STEP.powf(notches);
+
+    let moved = screen_to_world(app, model, cursor);
+    model.variables.view_params.x_shift += anchor.x - moved.x;
+    model.variables.view_params.y_shift += anchor.y - moved.y;
+    update_view_params_buffer(app, model);
+}
+
+// The left button steers the leader in its keyboard mode, so only right and middle pan there
+pub(crate) fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
+    let seeking = matches!(model.controls.kcm, KeyboardControlMode::Leader);
+
+    if button == MouseButton::Left {
+        let now = app.time;
+
+        if model
+            .controls
+            .last_click
+            .is_some_and(|last| now - last < DOUBLE_CLICK_TIME)
+        {
+            model.controls.last_click = None;
+            model.controls.click_start = None;
+            reset_view(app, model);
+            return;
+        }
+
+        model.controls.last_click = Some(now);
+
+        if !seeking {
+            model.controls.click_start = Some(app.mouse.position());
+        }
+    }
+
+    if button != MouseButton::Left || !seeking {
+        model.controls.drag = Some(app.mouse.position());
+    }
+}
+
+pub(crate) fn mouse_released(app: &App, model: &mut Model, button: MouseButton) {
+    model.controls.drag = None;
+
+    if button != MouseButton::Left {
+        return;
+    }
+
+    let position = app.mouse.position();
+
+    if let Some(start) = model.controls.click_start.take() {
+        if start.distance(position) < CLICK_SLOP {
+            select_agent_at(app, model, position);
+        }
+    }
+}
+
+// Whether point is over the pane of the simulation being edited, which has the agents to pick
+fn in_edited_pane(model: &Model, point: Vec2) -> bool {
+    if model.comparison.split.is_none() {
+        return true;
+    }
+
+    match model.comparison.editing {
+        ComparisonSide::A => point.x < 0.0,
+        ComparisonSide::B => point.x >= 0.0,
+    }
+}
+
+// Nearest live boid or predator of the simulation being edited, clicking empty space deselects.
+// Clicks on the other pane of a comparison are ignored, its agents aren't the ones read back
+fn select_agent_at(app: &App, model: &mut Model, point: Vec2) {
+    if !in_edited_pane(model, point) {
+        return;
+    }
+
+    let agents = &model.agents;
+    let target = screen_to_world(app, model, point);
+    let (uv_per_point, _) = pane_at(app, model, point);
+    let radius = PICK_RADIUS * uv_per_point / model.variables.view_params.zoom;
+
+    let live_boids = agents
+        .boids
+        .iter()
+        .zip(&agents.captured)
+        .enumerate()
+        .filter(|(_, (_, alive))| **alive > 0.5)
+        .map(|(id, (boid, _))| (SelectionKind::Boid, id, boid));
+    let all_predators = agents
+        .predators
+        .iter()
+        .enumerate()
+        .map(|(id, predator)| (SelectionKind::Predator, id, predator));
+
+    let nearest = live_boids
+        .chain(all_predators)
+        .map(|(kind, id, agent)| (kind, id, Vec2::from(agent.pos).distance(target)))
+        .filter(|(_, _, distance)| *distance < radius)
+        .min_by(|a, b| a.2.total_cmp(&b.2));
+
+    let op = &mut model.variables.overlay_params;
+    (op.selection, op.selected_id) = match nearest {
+        Some((kind, id, _)) => (kind as u32, id as u32),
+        None => (SelectionKind::None as u32, 0),
+    };
+    update_overlay_params_buffer(app, model);
+}
+
+pub(crate) fn mouse_moved(app: &App, model: &mut Model, position: Vec2) {
+    let Some(last) = model.controls.drag else {
+        return;
+    };
+
+    // Same scale in both panes, so a drag may cross between them
+    let (uv_per_point, _) = pane_at(app, model, position);
+    let vp = &mut model.variables.view_params;
+    let delta = (position - last) * uv_per_point / vp.zoom;
+
+    vp.x_shift -= delta.x;
+    vp.y_shift -= delta.y;
+    model.controls.drag = Some(position);
+    update_view_params_buffer(app, model);
+}
+
+fn reset_view(app: &App, model: &mut Model) {
+    let vp = &mut model.variables.view_params;
+    vp.x_shift = 0.0;
+    vp.y_shift = 0.0;
+    vp.zoom = INITIAL_ZOOM;
+    update_view_params_buffer(app, model);
+}
@@ -0,0 +1,65 @@
+use nannou::{prelude::*, App};
+
+use super::mouse_controls::pane_size;
+use crate::Model;
+
+// The bar is the smallest 1, 2 or 5 times a power of ten world units at least this long
+const SCALE_BAR_MIN_POINTS: f32 = 80.0;
+// Distance from the bottom left corner of the window, in points
+const SCALE_BAR_MARGIN: f32 = 20.0;
+
+// Same as grid_spacing in view.wgsl, also returns the power of ten the spacing is a multiple of so
+// labels show exactly the digits needed
+pub(crate) fn grid_spacing(world_per_point: f32, min_points: f32) -> (f32, i32) {
+    let min_world = min_points.max(1.0) * world_per_point;
+    let exponent = min_world.log10().floor() as i32;
+    let step = 10f32.powi(exponent);
+
+    let spacing = [1.0, 2.0, 5.0]
+        .into_iter()
+        .map(|m| m * step)
+        .find(|s| *s >= min_world)
+        .unwrap_or(10.0 * step);
+
+    (spacing, exponent)
+}
+
+// Drawn with nannou on top of the post processed frame, so only the window gets one
+pub(crate) fn draw_scale_bar(app: &App, model: &Model, frame: &Frame) {
+    if model.variables.grid_params.scale_bar == 0 {
+        return;
+    }
+
+    let window = app.window(model.main_wid).unwrap();
+    let (w, h) = window.inner_size_points();
+    let vp = &model.variables.view_params;
+
+    // pixel_size in view.wgsl, in points rather than pixels. Both panes of a comparison share it
+    let world_per_point = 2.0 / (pane_size(app, model).max_element() * vp.zoom);
+    let (spacing, exponent) = grid_spacing(world_per_point, SCALE_BAR_MIN_POINTS);
+    let length = spacing / world_per_point;
+    let label = format!("{:.*} units", (-exponent).max(0) as usize, spacing);
+
+    let start = pt2(-w / 2.0 + SCALE_BAR_MARGIN, -h / 2.0 + SCALE_BAR_MARGIN);
+    let end = start + vec2(length, 0.0);
+    let color = rgba(1.0, 1.0, 1.0, 0.85);
+
+    let draw = app.draw();
+    draw.line().start(start).end(end).weight(2.0).color(color);
+
+    for tick in [start, end] {
+        draw.line()
+            .start(tick)
+            .end(tick + vec2(0.0, 6.0))
+            .weight(2.0)
+            .color(color);
+    }
+
+    draw.text(&label)
+        .xy(start + vec2(length / 2.0, 16.0))
+        .w_h(length.max(120.0), 20.0)
+        .font_size(13)
+        .color(color);
+
+    draw.to_frame(app, frame).unwrap();
+}
@@ -0,0 +1,179 @@
+use std::mem;
+
+use nannou::{
+    wgpu::{self, util::DeviceExt},
+    App,
+};
+
+use super::update_buffers::reset_simulation;
+use crate::{
+    create_post_targets, create_simulation, create_trail_targets, ComparisonSide, Model,
+    ResolutionUniform, ScenePane, SplitView,
+};
+
+// Left and right halves of the framebuffer as x, y, width and height in pixels
+fn pane_rects(size: [u32; 2]) -> [[u32; 4]; 2] {
+    let [width, height] = size;
+    let left = width / 2;
+    [[0, 0, left, height], [left, 0, width - left, height]]
+}
+
+// Each pane gets its own resolution so it maps its pixels to the world like a window of that size
+fn create_pane_bind_groups(app: &App, model: &Model) -> [wgpu::BindGroup; 2] {
+    let window = app.window(model.main_wid).unwrap();
+    let device = window.device();
+    let resolution = model.uniforms.resolution;
+
+    pane_rects(resolution.xy.map(|v| v as u32)).map(|[x, y, w, h]| {
+        let pane_resolution = ResolutionUniform {
+            xy: [w as f32, h as f32],
+            origin: [x as f32, y as f32],
+            scale_factor: resolution.scale_factor,
+            _padding: 0.0,
+        };
+
+        let resolution_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Pane Resolution Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[pane_resolution]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &model.layouts.uniform_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: model.uniforms.time_uniform.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: resolution_uniform.as_entire_binding(),
+                },
+            ],
+            label: Some("pane_uniforms_bind_group"),
+        })
+    })
+}
+
+// B starts with A's parameters, and both are reset so they run from the same seed and step
+pub(crate) fn start_comparison(app: &App, model: &mut Model) {
+    if model.comparison.split.is_some() {
+        return;
+    }
+
+    let window = app.window(model.main_wid).unwrap();
+    let device = window.device();
+    let size = model.uniforms.resolution.xy.map(|v| v as u32);
+
+    let other = create_simulation(
+        device,
+        &model.layouts,
+        &model.uniforms,
+        &model.variables.density_params_uniform,
+        model.sim.params,
+    );
+    let trails = create_trail_targets(device, &model.layouts, &model.trails.params_uniform, size);
+    let post = create_post_targets(
+        device,
+        &model.layouts,
+        &model.post.params_uniform,
+        &model.post.sampler,
+        size,
+        window.msaa_samples(),
+    );
+    let pane_bind_groups = create_pane_bind_groups(app, model);
+
+    model.comparison.editing = ComparisonSide::A;
+    model.comparison.split = Some(SplitView {
+        other,
+        trails,
+        post,
+        pane_bind_groups,
+    });
+
+    reset_simulation(app, model);
+}
+
+// Keeps A running on its own
+pub(crate) fn stop_comparison(model: &mut Model) {
+    set_editing(model, ComparisonSide::A);
+    model.comparison.split = None;
+}
+
+// Swaps the other simulation into Model.sim, so every control acts on side
+pub(crate) fn set_editing(model: &mut Model, side: ComparisonSide) {
+    if model.comparison.editing == side {
+        return;
+    }
+
+    let Some(split) = model.comparison.split.as_mut() else {
+        return;
+    };
+
+    mem::swap(&mut model.sim, &mut split.other);
+    mem::swap(&mut model.trails.targets, &mut split.trails);
+    mem::swap(&mut model.post.targets, &mut split.post);
+    model.comparison.editing = side;
+}
+
+// Runs f with the other simulation in Model.sim, does nothing unless comparing
+pub(crate) fn with_other_simulation(model: &mut Model, f: impl FnOnce(&mut Model)) {
+    if model.comparison.split.is_none() {
+        return;
+    }
+
+    let editing = model.comparison.editing;
+    set_editing(model, editing.other());
+    f(model);
+    set_editing(model, editing);
+}
+
+// Follows update_resolution, the split targets are per pixel like the window's own
+pub(crate) fn update_split_resolution(app: &App, model: &mut Model) {
+    if model.comparison.split.is_none() {
+        return;
+    }
+
+    let window = app.window(model.main_wid).unwrap();
+    let device = window.device();
+    let size = model.uniforms.resolution.xy.map(|v| v as u32);
+
+    let trails = create_trail_targets(device, &model.layouts, &model.trails.params_uniform, size);
+    let post = create_post_targets(
+        device,
+        &model.layouts,
+        &model.post.params_uniform,
+        &model.post.sampler,
+        size,
+        window.msaa_samples(),
+    );
+    let pane_bind_groups = create_pane_bind_groups(app, model);
+
+    if let Some(split) = &mut model.comparison.split {
+        split.trails = trails;
+        split.post = post;
+        split.pane_bind_groups = pane_bind_groups;
+    }
+}
+
+// A on the left and B on the right, whichever of them Model.sim holds
+pub(crate) fn comparison_panes<'a>(model: &'a Model, split: &'a SplitView) -> [ScenePane<'a>; 2] {
+    let edited = (&model.sim, &model.trails.targets, &model.post.targets);
+    let other = (&split.other, &split.trails, &split.post);
+    let sides = match model.comparison.editing {
+        ComparisonSide::A => [edited, other],
+        ComparisonSide::B => [other, edited],
+    };
+    let rects = pane_rects(model.uniforms.resolution.xy.map(|v| v as u32));
+
+    [0, 1].map(|i| {
+        let (sim, trails, post) = sides[i];
+        ScenePane {
+            sim,
+            uniform_bind_group: &split.pane_bind_groups[i],
+            trails,
+            post,
+            scissor: Some(rects[i]),
+        }
+    })
+}
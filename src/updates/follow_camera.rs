@@ -0,0 +1,100 @@
+use nannou::{event::Update, glam::Vec2, App};
+
+use crate::{
+    mouse_controls::pane_size, update_view_params_buffer, Boid, FollowMode, Model,
+    SEQUENCE_STEP_TIME,
+};
+
+// Pursuits entry of a predator without a target
+const NO_TARGET: u32 = 0xFFFFFFFF;
+
+// World position the camera should centre on, None when the mode has nothing to follow
+fn follow_target(
+    model: &Model,
+    boids: &[Boid],
+    predators: &[Boid],
+    pursuits: &[u32],
+    live: &[Vec2],
+) -> Option<Vec2> {
+    let follow = &model.follow;
+    let pos = |agent: &Boid| Vec2::from(agent.pos);
+
+    match follow.mode {
+        FollowMode::Off => None,
+        FollowMode::Predator => predators.get(follow.predator).map(pos),
+        FollowMode::Boid => boids.get(follow.boid).map(pos),
+        FollowMode::Centroid => {
+            (!live.is_empty()).then(|| live.iter().sum::<Vec2>() / live.len() as f32)
+        }
+        FollowMode::PredatorTarget => match pursuits.get(follow.predator) {
+            Some(&target) if target != NO_TARGET => boids.get(target as usize).map(pos),
+            _ => predators.get(follow.predator).map(pos),
+        },
+    }
+}
+
+// Eases the camera towards the followed agent from this frame's agent snapshot
+pub(crate) fn update_follow_camera(app: &App, model: &mut Model, update: Update) {
+    if model.follow.mode == FollowMode::Off {
+        return;
+    }
+
+    let agents = &model.agents;
+
+    // captured_boids_buf holds 1.0 for boids still in play
+    let live: Vec<Vec2> = agents
+        .boids
+        .iter()
+        .zip(&agents.captured)
+        .filter(|(_, alive)| **alive > 0.5)
+        .map(|(boid, _)| Vec2::from(boid.pos))
+        .collect();
+
+    let Some(target) = follow_target(
+        model,
+        &agents.boids,
+        &agents.predators,
+        &agents.pursuits,
+        &live,
+    ) else {
+        return;
+    };
+
+    // Frame rate independent exponential smoothing, a lag of 0 snaps straight to the target.
+    // Captured frames are one fixed step apart however long they took to render
+    let capturing = model.frame_sequence.run.is_some() || model.recorder.recording.is_some();
+    let dt = if capturing {
+        SEQUENCE_STEP_TIME
+    } else {
+        update.since_last.as_secs_f32()
+    };
+    let lag = model.follow.lag;
+    let t = if lag > 0.0 {
+        1.0 - (-dt / lag).exp()
+    } else {
+        1.0
+    };
+
+    let vp = &mut model.variables.view_params;
+    vp.x_shift += (target.x - vp.x_shift) * t;
+    vp.y_shift += (target.y - vp.y_shift) * t;
+
+    if model.follow.auto_zoom && !live.is_empty() {
+        // Furthest any live boid is from the target on each axis
+        let extent = live
+            .iter()
+            .fold(Vec2::ZERO, |extent, p| extent.max((*p - target).abs()))
+            + Vec2::splat(model.follow.margin);
+
+        // scale_aspect spans size / longest side either way of the centre in uv
+        let size = pane_size(app, model);
+        let half_uv = size / size.max_element();
+        let fit = (half_uv / extent.max(Vec2::splat(0.001))).min_element();
+
+        // Eased in log space so zooming in and out feel the same
+        let vp = &mut model.variables.view_params;
+        vp.zoom *= (fit / vp.zoom).powf(t);
+    }
+
+    update_view_params_buffer(app, model);
+}
@@ -0,0 +1,92 @@
+use std::{error::Error, fs, path::Path};
+
+use crate::{Boid, NUM_BOIDS};
+
+// One boid per line as `x,y` or `x,y,vx,vy`, after an optional header line. Blank lines are
+// skipped, any other line that doesn't parse is an error
+pub(crate) fn load_formation_csv(path: &Path) -> Result<Vec<Boid>, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)?;
+    let mut boids = Vec::with_capacity(NUM_BOIDS);
+    let mut first_row = true;
+
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let header_allowed = first_row;
+        first_row = false;
+
+        let values: Result<Vec<f32>, _> = line.split(',').map(|v| v.trim().parse()).collect();
+
+        let boid = match values.as_deref() {
+            Ok([x, y]) => Boid {
+                pos: [*x, *y],
+                vel: [0.0, 0.0],
+            },
+            Ok([x, y, vx, vy]) => Boid {
+                pos: [*x, *y],
+                vel: [*vx, *vy],
+            },
+            _ if header_allowed => continue,
+            _ => {
+                return Err(format!(
+                    "{} line {}: expected x,y or x,y,vx,vy, found {line:?}",
+                    path.display(),
+                    i + 1
+                )
+                .into())
+            }
+        };
+
+        boids.push(boid);
+
+        if boids.len() == NUM_BOIDS {
+            break;
+        }
+    }
+
+    if boids.is_empty() {
+        return Err(format!("no boids found in {}", path.display()).into());
+    }
+
+    Ok(boids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(name: &str, contents: &str) -> Result<Vec<Boid>, Box<dyn Error>> {
+        let path =
+            std::env::temp_dir().join(format!("formation-{}-{name}.csv", std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let boids = load_formation_csv(&path);
+        fs::remove_file(&path).unwrap();
+        boids
+    }
+
+    #[test]
+    fn header_line_is_skipped() {
+        let boids = load("header", "x,y,vx,vy\n1,2,3,4\n5, 6\n").unwrap();
+
+        assert_eq!(boids.len(), 2);
+        assert_eq!((boids[0].pos, boids[0].vel), ([1.0, 2.0], [3.0, 4.0]));
+        assert_eq!((boids[1].pos, boids[1].vel), ([5.0, 6.0], [0.0, 0.0]));
+    }
+
+    #[test]
+    fn malformed_row_reports_its_line() {
+        let error = load("malformed", "x,y\n1,2\n\n3,oops\n").unwrap_err();
+
+        assert!(error.to_string().contains("line 4"), "{error}");
+    }
+
+    #[test]
+    fn short_file_loads_the_rows_it_has() {
+        let boids = load("short", "1,2\n3,4\n").unwrap();
+
+        assert_eq!(boids.len(), 2);
+        assert!(load("empty", "x,y\n").is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use futures::executor::block_on;
+use nannou::{frame::Frame, wgpu, App};
+
+use crate::{
+    create_compute_pipeline, create_post_pipeline, create_render_pipeline,
+    misc::wgsl::{compose_shader, embedded_source, shader_module_from_source},
+    Layouts, Model, Pipelines, POST_FORMAT, TRAIL_FORMAT,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Every shader that a pipeline is built from, includes are picked up through these
+pub(crate) const PIPELINE_SHADERS: [&str; 10] = [
+    "vertex/v2.wgsl",
+    "fragment/boids_frag.wgsl",
+    "fragment/trails.wgsl",
+    "fragment/post.wgsl",
+    "compute/init_boids.wgsl",
+    "compute/init_predator.wgsl",
+    "compute/seperation_alignment_cohesion.wgsl",
+    "compute/predator_chase_path.wgsl",
+    "compute/forage.wgsl",
+    "compute/density.wgsl",
+];
+
+fn shader_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("src/shaders")
+}
+
+fn load_from_disk(path: &str) -> Option<String> {
+    fs::read_to_string(shader_dir().join(path)).ok()
+}
+
+fn collect_modified(dir: &Path, modified: &mut HashMap<PathBuf, SystemTime>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_modified(&path, modified);
+        } else if let Ok(time) = entry.metadata().and_then(|m| m.modified()) {
+            modified.insert(path, time);
+        }
+    }
+}
+
+pub(crate) fn initial_shader_sources() -> HashMap<&'static str, String> {
+    PIPELINE_SHADERS
+        .iter()
+        .map(|path| (*path, compose_shader(path, &embedded_source).unwrap()))
+        .collect()
+}
+
+// Runs build inside a validation error scope so a broken shader is reported instead of panicking
+fn validated<T>(
+    device: &wgpu::Device,
+    build: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let built = build();
+
+    match block_on(device.pop_error_scope()) {
+        Some(error) => Err(error.to_string()),
+        None => built,
+    }
+}
+
+fn load_module(device: &wgpu::Device, path: &str) -> Result<wgpu::ShaderModule, String> {
+    let source = compose_shader(path, &load_from_disk)?;
+    Ok(shader_module_from_source(device, path, source))
+}
+
+fn rebuild_compute(
+    device: &wgpu::Device,
+    layouts: &Layouts,
+    label: &str,
+    path: &str,
+    entry_point: &str,
+) -> Result<wgpu::ComputePipeline, String> {
+    validated(device, || {
+        let module = load_module(device, path)?;
+        Ok(create_compute_pipeline(
+            device,
+            layouts,
+            label,
+            &module,
+            entry_point,
+        ))
+    })
+}
+
+// Only the pipelines built from the changed shader are replaced, and only once they validate
+fn rebuild_pipelines(
+    device: &wgpu::Device,
+    layouts: &Layouts,
+    sample_count: u32,
+    pipelines: &mut Pipelines,
+    path: &str,
+) -> Result<(), String> {
+    match path {
+        "vertex/v2.wgsl" => {
+            let (render, trail) = validated(device, || {
+                let vs_mod = load_module(device, path)?;
+                let boids_mod = load_module(device, "fragment/boids_frag.wgsl")?;
+                let trails_mod = load_module(device, "fragment/trails.wgsl")?;
+                Ok((
+                    create_render_pipeline(
+                        device,
+                        layouts,
+                        &vs_mod,
+                        &boids_mod,
+                        Frame::TEXTURE_FORMAT,
+                        sample_count,
+                    ),
+                    create_render_pipeline(device, layouts, &vs_mod, &trails_mod, TRAIL_FORMAT, 1),
+                ))
+            })?;
+            pipelines.render_pipeline = render;
+            pipelines.trail_pipeline = trail;
+        }
+        "fragment/boids_frag.wgsl" => {
+            pipelines.render_pipeline = validated(device, || {
+                let vs_mod = load_module(device, "vertex/v2.wgsl")?;
+                let fs_mod = load_module(device, path)?;
+                Ok(create_render_pipeline(
+                    device,
+                    layouts,
+                    &vs_mod,
+                    &fs_mod,
+                    Frame::TEXTURE_FORMAT,
+                    sample_count,
+                ))
+            })?;
+        }
+        "fragment/trails.wgsl" => {
+            pipelines.trail_pipeline = validated(device, || {
+                let vs_mod = load_module(device, "vertex/v2.wgsl")?;
+                let fs_mod = load_module(device, path)?;
+                Ok(create_render_pipeline(
+                    device,
+                    layouts,
+                    &vs_mod,
+                    &fs_mod,
+                    TRAIL_FORMAT,
+                    1,
+                ))
+            })?;
+        }
+        "fragment/post.wgsl" => {
+            let [bright, blur_h, blur_v, composite] = validated(device, || {
+                let module = load_module(device, path)?;
+                let post_pipeline = |entry_point, format, sample_count| {
+                    create_post_pipeline(
+                        device,
+                        layouts,
+                        &module,
+                        entry_point,
+                        format,
+                        sample_count,
+                    )
+                };
+                Ok([
+                    post_pipeline("bright_pass", POST_FORMAT, 1),
+                    post_pipeline("blur_horizontal", POST_FORMAT, 1),
+                    post_pipeline("blur_vertical", POST_FORMAT, 1),
+                    post_pipeline("composite", Frame::TEXTURE_FORMAT, sample_count),
+                ])
+            })?;
+            pipelines.post_bright_pipeline = bright;
+            pipelines.post_blur_h_pipeline = blur_h;
+            pipelines.post_blur_v_pipeline = blur_v;
+            pipelines.post_composite_pipeline = composite;
+        }
+        "compute/init_boids.wgsl" => {
+            pipelines.compute_boid_pos_pipeline = rebuild_compute(
+                device,
+                layouts,
+                "Compute Boid Position Pipeline",
+                path,
+                "compute_boid_positions",
+            )?;
+        }
+        "compute/init_predator.wgsl" => {
+            pipelines.compute_predator_pos_pipeline = rebuild_compute(
+                device,
+                layouts,
+                "Compute Predator Position Pipeline",
+                path,
+                "compute_predator_position",
+            )?;
+        }
+        "compute/seperation_alignment_cohesion.wgsl" => {
+            let (alarm, sac) = validated(device, || {
+                let module = load_module(device, path)?;
+                Ok((
+                    create_compute_pipeline(
+                        device,
+                        layouts,
+                        "Compute Alarm Pipeline",
+                        &module,
+                        "spread_alarm",
+                    ),
+                    create_compute_pipeline(
+                        device,
+                        layouts,
+                        "Compute SAC Pipeline",
+                        &module,
+                        "sac",
+                    ),
+                ))
+            })?;
+            pipelines.compute_alarm_pipeline = alarm;
+            pipelines.compute_sac_pipeline = sac;
+        }
+        "compute/predator_chase_path.wgsl" => {
+            pipelines.compute_pursuit_curve_pipeline = rebuild_compute(
+                device,
+                layouts,
+                "Compute Predator Pursuit Pipeline",
+                path,
+                "compute_predator_pursuit",
+            )?;
+        }
+        "compute/forage.wgsl" => {
+            let (forage, food) = validated(device, || {
+                let module = load_module(device, path)?;
+                Ok((
+                    create_compute_pipeline(
+                        device,
+                        layouts,
+                        "Compute Forage Pipeline",
+                        &module,
+                        "forage",
+                    ),
+                    create_compute_pipeline(
+                        device,
+                        layouts,
+                        "Compute Food Regrowth Pipeline",
+                        &module,
+                        "regrow_food",
+                    ),
+                ))
+            })?;
+            pipelines.compute_forage_pipeline = forage;
+            pipelines.compute_food_pipeline = food;
+        }
+        "compute/density.wgsl" => {
+            pipelines.compute_density_pipeline = rebuild_compute(
+                device,
+                layouts,
+                "Compute Density Pipeline",
+                path,
+                "splat_density",
+            )?;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+pub(crate) fn poll_shader_changes(app: &App, model: &mut Model) {
+    let hr = &mut model.hot_reload;
+
+    if !hr.enabled || hr.last_poll.elapsed() < POLL_INTERVAL {
+        return;
+    }
+
+    hr.last_poll = Instant::now();
+
+    let mut modified = HashMap::new();
+    collect_modified(&shader_dir(), &mut modified);
+
+    if modified == hr.modified {
+        return;
+    }
+
+    hr.modified = modified;
+
+    let window = app.window(model.main_wid).unwrap();
+    let device = window.device();
+    let sample_count = window.msaa_samples();
+
+    for path in PIPELINE_SHADERS {
+        let source = match compose_shader(path, &load_from_disk) {
+            Ok(source) => source,
+            Err(error) => {
+                model.hot_reload.errors.insert(path, error);
+                continue;
+            }
+        };
+
+        // Saving a file without changing it, or reverting a broken edit, leaves the pipeline as is
+        if model.hot_reload.sources.get(path) == Some(&source) {
+            model.hot_reload.errors.remove(path);
+            continue;
+        }
+
+        match rebuild_pipelines(
+            device,
+            &model.layouts,
+            sample_count,
+            &mut model.pipelines,
+            path,
+        ) {
+            Ok(()) => {
+                println!("Reloaded shader: {path}");
+                model.hot_reload.sources.insert(path, source);
+                model.hot_reload.errors.remove(path);
+            }
+            Err(error) => {
+                eprintln!("Error reloading shader {path}:\n{error}");
+                model.hot_reload.errors.insert(path, error);
+            }
+        }
+    }
+}
@@ -1,3 +1,10 @@
+pub(crate) mod comparison;
+pub(crate) mod follow_camera;
+pub(crate) mod formations;
+pub(crate) mod hot_reload;
+pub(crate) mod offscreen;
+pub(crate) mod recorder;
+pub(crate) mod svg_export;
 pub(crate) mod update_buffers;
 
 pub(crate) use update_buffers::*;
@@ -0,0 +1,278 @@
+use std::{fs, sync::mpsc};
+
+use nannou::{
+    event::Update,
+    frame::Frame,
+    wgpu::{self, util::DeviceExt},
+    App,
+};
+
+use super::update_buffers::{reset_simulation, step_simulations};
+use crate::{
+    create_post_targets, create_trail_targets, encode_scene, Model, OffscreenTarget,
+    ResolutionUniform, ScenePane, SequenceRun, SEQUENCE_STEP_TIME,
+};
+
+pub(crate) fn create_offscreen_target(app: &App, model: &Model, size: [u32; 2]) -> OffscreenTarget {
+    let window = app.window(model.main_wid).unwrap();
+    let device = window.device();
+    let sample_count = window.msaa_samples();
+
+    // Screen sized glyphs keep their size relative to the frame, so a 4K render looks like the
+    // window scaled up rather than the same pixels on a bigger canvas
+    let window_resolution = model.uniforms.resolution;
+    let scale =
+        size[0].max(size[1]) as f32 / f32::max(window_resolution.xy[0], window_resolution.xy[1]);
+    let resolution = ResolutionUniform {
+        xy: [size[0] as f32, size[1] as f32],
+        origin: [0.0, 0.0],
+        scale_factor: window_resolution.scale_factor * scale,
+        _padding: 0.0,
+    };
+
+    let time_uniform = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Offscreen Time Uniform Buffer"),
+        size: std::mem::size_of::<f32>() as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let resolution_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Offscreen Resolution Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[resolution]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &model.layouts.uniform_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: time_uniform.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: resolution_uniform.as_entire_binding(),
+            },
+        ],
+        label: Some("offscreen_uniforms_bind_group"),
+    });
+
+    // Same format and sample count as the window frame so the render pipeline can be shared
+    let texture = wgpu::TextureBuilder::new()
+        .size(size)
+        .format(Frame::TEXTURE_FORMAT)
+        .usage(
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+        )
+        .build(device);
+    let view = texture.view().build();
+
+    let msaa_texture = (sample_count > 1).then(|| {
+        wgpu::TextureBuilder::new()
+            .size(size)
+            .format(Frame::TEXTURE_FORMAT)
+            .sample_count(sample_count)
+            .usage(wgpu::TextureUsages::RENDER_ATTACHMENT)
+            .build(device)
+    });
+
+    let trails = create_trail_targets(device, &model.layouts, &model.trails.params_uniform, size);
+    let post = create_post_targets(
+        device,
+        &model.layouts,
+        &model.post.params_uniform,
+        &model.post.sampler,
+        size,
+        sample_count,
+    );
+
+    OffscreenTarget {
+        size,
+        time_uniform,
+        uniform_bind_group,
+        msaa_texture,
+        texture,
+        view,
+        trails,
+        post,
+    }
+}
+
+// Records the scene into the target's texture, the caller submits the encoder
+pub(crate) fn encode_offscreen(
+    app: &App,
+    model: &Model,
+    target: &OffscreenTarget,
+    time: f32,
+    encoder: &mut wgpu::CommandEncoder,
+) {
+    let window = app.window(model.main_wid).unwrap();
+
+    window
+        .queue()
+        .write_buffer(&target.time_uniform, 0, &time.to_ne_bytes());
+
+    // Always the simulation being edited, the A/B split is only drawn in the window
+    let pane = ScenePane {
+        sim: &model.sim,
+        uniform_bind_group: &target.uniform_bind_group,
+        trails: &target.trails,
+        post: &target.post,
+        scissor: None,
+    };
+
+    match &target.msaa_texture {
+        Some(msaa_texture) => {
+            let msaa_view = msaa_texture.view().build();
+            encode_scene(encoder, model, &pane, &msaa_view, Some(&target.view));
+        }
+        None => encode_scene(encoder, model, &pane, &target.view, None),
+    }
+}
+
+// Resets the simulation so step numbers always refer to the same state for a given seed, then
+// runs up to the first step without rendering
+pub(crate) fn start_frame_sequence(app: &App, model: &mut Model, update: Update) {
+    if model.comparison.split.is_some() {
+        println!("Frame sequences are unavailable in split view, end the comparison first");
+        return;
+    }
+
+    let seq = &model.frame_sequence;
+    let dir = app
+        .project_path()
+        .expect("failed to locate project directory")
+        .join(&seq.output_dir);
+
+    if let Err(error) = fs::create_dir_all(&dir) {
+        println!("Error creating {}: {error}", dir.display());
+        return;
+    }
+
+    let first_step = seq.first_step.min(seq.last_step);
+    let target = create_offscreen_target(app, model, seq.size);
+
+    reset_simulation(app, model);
+
+    for _ in 0..first_step {
+        step_simulations(app, model, update);
+    }
+
+    println!(
+        "Rendering steps {first_step} to {} at {}x{} into {}",
+        model.frame_sequence.last_step,
+        target.size[0],
+        target.size[1],
+        dir.display()
+    );
+
+    let (error_sender, errors) = mpsc::channel();
+
+    model.frame_sequence.run = Some(SequenceRun {
+        step: first_step,
+        dir,
+        target,
+        capturer: wgpu::TextureCapturer::default(),
+        error_sender,
+        errors,
+    });
+}
+
+// Renders and saves the current step, then advances the simulation by exactly one step. Takes
+// the place of the regular simulation update while a sequence is running.
+pub(crate) fn update_frame_sequence(app: &App, model: &mut Model, update: Update) {
+    let Some(run) = &model.frame_sequence.run else {
+        return;
+    };
+
+    // A frame that failed to save ends the sequence, the rest would be missing it
+    if let Ok(error) = run.errors.try_recv() {
+        println!("Error writing frame sequence: {error}");
+        stop_frame_sequence(app, model);
+        return;
+    }
+
+    let window = app.window(model.main_wid).unwrap();
+    let device = window.device();
+    let step = run.step;
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("frame sequence encoder"),
+    });
+
+    encode_offscreen(
+        app,
+        model,
+        &run.target,
+        step as f32 * SEQUENCE_STEP_TIME,
+        &mut encoder,
+    );
+
+    let snapshot = run
+        .capturer
+        .capture(device, &mut encoder, &run.target.texture);
+    window.queue().submit(Some(encoder.finish()));
+
+    let path = run.dir.join(format!("{step:06}.png"));
+    let errors = run.error_sender.clone();
+    let read = snapshot.read(move |result| {
+        let saved = match result {
+            Ok(image) => image.to_owned().save(&path).map_err(|e| e.to_string()),
+            Err(error) => Err(format!("{error:?}")),
+        };
+
+        if let Err(error) = saved {
+            let _ = errors.send(format!("{}: {error}", path.display()));
+        }
+    });
+
+    if let Err(error) = read {
+        println!("Error capturing step {step}: {error:?}");
+        stop_frame_sequence(app, model);
+        return;
+    }
+
+    if step >= model.frame_sequence.last_step {
+        stop_frame_sequence(app, model);
+        return;
+    }
+
+    step_simulations(app, model, update);
+
+    if let Some(run) = &mut model.frame_sequence.run {
+        run.step += 1;
+    }
+}
+
+// Waits for the frames still being written before dropping the target
+pub(crate) fn stop_frame_sequence(app: &App, model: &mut Model) {
+    let Some(run) = model.frame_sequence.run.take() else {
+        return;
+    };
+
+    let window = app.window(model.main_wid).unwrap();
+    let mut complete = run.step >= model.frame_sequence.last_step;
+
+    if let Err(error) = run.capturer.await_active_snapshots(window.device()) {
+        println!("Error writing frame sequence: {error:?}");
+        complete = false;
+    }
+
+    for error in run.errors.try_iter() {
+        println!("Error writing frame sequence: {error}");
+        complete = false;
+    }
+
+    if complete {
+        println!("Frame sequence written to {}", run.dir.display());
+    } else {
+        println!(
+            "Frame sequence stopped at step {}, the frames so far are in {}",
+            run.step,
+            run.dir.display()
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc::{self, Receiver, TrySendError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use nannou::{
+    image::{
+        codecs::gif::{GifEncoder, Repeat},
+        Delay, Frame, ImageError, RgbaImage,
+    },
+    wgpu, App,
+};
+
+use super::offscreen::{create_offscreen_target, encode_offscreen};
+use crate::{FinishingRecording, Model, RecordSource, Recording, VideoFormat};
+
+// The simulation advances one step per update, videos are timed as if that ran at 60 fps
+const SIM_FPS: u32 = 60;
+// Memory for captured frames waiting on the writer, any more are dropped rather than piling up
+const MAX_QUEUED_BYTES: usize = 256 * 1024 * 1024;
+
+enum VideoWriter {
+    Y4m(BufWriter<File>),
+    Gif(GifEncoder<BufWriter<File>>, Delay),
+}
+
+fn image_error(error: ImageError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, error)
+}
+
+impl VideoWriter {
+    fn create(
+        path: &Path,
+        format: VideoFormat,
+        size: [u32; 2],
+        decimation: u32,
+    ) -> io::Result<VideoWriter> {
+        let mut file = BufWriter::new(File::create(path)?);
+
+        match format {
+            VideoFormat::Y4m => {
+                writeln!(
+                    file,
+                    "YUV4MPEG2 W{} H{} F{SIM_FPS}:{decimation} Ip A1:1 C444 XCOLORRANGE=FULL",
+                    size[0], size[1]
+                )?;
+                Ok(VideoWriter::Y4m(file))
+            }
+            VideoFormat::Gif => {
+                let mut encoder = GifEncoder::new(file);
+                encoder.set_repeat(Repeat::Infinite).map_err(image_error)?;
+                let delay = Delay::from_numer_denom_ms(1000 * decimation, SIM_FPS);
+                Ok(VideoWriter::Gif(encoder, delay))
+            }
+        }
+    }
+
+    fn write_frame(&mut self, image: RgbaImage) -> io::Result<()> {
+        match self {
+            VideoWriter::Y4m(file) => write_y4m_frame(file, &image),
+            VideoWriter::Gif(encoder, delay) => encoder
+                .encode_frame(Frame::from_parts(image, 0, 0, *delay))
+                .map_err(image_error),
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            VideoWriter::Y4m(mut file) => file.flush(),
+            // The gif trailer is written when the encoder is dropped
+            VideoWriter::Gif(..) => Ok(()),
+        }
+    }
+}
+
+// Full range BT.601, matching the XCOLORRANGE=FULL header
+fn write_y4m_frame(out: &mut impl Write, image: &RgbaImage) -> io::Result<()> {
+    let n = (image.width() * image.height()) as usize;
+    let mut planes = vec![0u8; 3 * n];
+
+    for (i, pixel) in image.pixels().enumerate() {
+        let [r, g, b] = [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32];
+
+        planes[i] = (0.299 * r + 0.587 * g + 0.114 * b).round() as u8;
+        planes[n + i] = (128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b).round() as u8;
+        planes[2 * n + i] = (128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b).round() as u8;
+    }
+
+    out.write_all(b"FRAME\n")?;
+    out.write_all(&planes)
+}
+
+// Half the budget each for the channel and the writer's reorder buffer, at least a frame apiece
+fn max_queued_frames(size: [u32; 2]) -> usize {
+    let frame_bytes = 4 * size[0] as usize * size[1] as usize;
+    (MAX_QUEUED_BYTES / 2 / frame_bytes.max(1)).max(1)
+}
+
+// Encoding happens off the main thread so recording doesn't stall the simulation
+fn spawn_writer(
+    path: PathBuf,
+    format: VideoFormat,
+    size: [u32; 2],
+    decimation: u32,
+    frames: Receiver<(u32, RgbaImage)>,
+) -> JoinHandle<io::Result<()>> {
+    let max_pending = max_queued_frames(size);
+
+    thread::spawn(move || {
+        let mut writer = VideoWriter::create(&path, format, size, decimation)?;
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+
+        for (index, image) in frames {
+            pending.insert(index, image);
+
+            // A missing frame was dropped or failed to capture, so stop waiting on it once
+            // enough later ones have arrived
+            while pending
+                .first_key_value()
+                .is_some_and(|(&index, _)| index == next || pending.len() > max_pending)
+            {
+                if let Some((index, image)) = pending.pop_first() {
+                    next = index + 1;
+                    writer.write_frame(image)?;
+                }
+            }
+        }
+
+        for image in pending.into_values() {
+            writer.write_frame(image)?;
+        }
+
+        writer.finish()
+    })
+}
+
+pub(crate) fn start_recording(app: &App, model: &mut Model) {
+    if model.comparison.split.is_some() {
+        println!("Recording is unavailable in split view, end the comparison first");
+        return;
+    }
+
+    let rec = &model.recorder;
+    let dir = app
+        .project_path()
+        .expect("failed to locate project directory")
+        .join(&rec.output_dir);
+
+    if let Err(error) = fs::create_dir_all(&dir) {
+        println!("Error creating {}: {error}", dir.display());
+        return;
+    }
+
+    let size = match rec.source {
+        RecordSource::Window => model.uniforms.resolution.xy.map(|v| v as u32),
+        RecordSource::Offscreen => rec.size,
+    };
+    let decimation = rec.decimation.max(1);
+    let max_frames = (rec.max_seconds > 0.0).then(|| {
+        let frames = rec.max_seconds * SIM_FPS as f32 / decimation as f32;
+        (frames.ceil() as u32).max(1)
+    });
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = dir.join(format!("recording-{stamp}.{}", rec.format.extension()));
+
+    let (frame_sender, frames) = mpsc::sync_channel(max_queued_frames(size));
+    let writer = spawn_writer(path.clone(), rec.format, size, decimation, frames);
+    let target = create_offscreen_target(app, model, size);
+
+    println!("Recording {}x{} to {}", size[0], size[1], path.display());
+
+    model.recorder.recording = Some(Recording {
+        path,
+        target,
+        capturer: wgpu::TextureCapturer::default(),
+        frames_seen: 0,
+        frames_kept: 0,
+        max_frames,
+        frame_sender,
+        frames_dropped: Arc::default(),
+        writer,
+    });
+}
+
+// Captures the frame the simulation just stepped to, if decimation keeps it
+pub(crate) fn update_recording(app: &App, model: &mut Model) {
+    let decimation = model.recorder.decimation.max(1);
+
+    report_finished_recordings(model);
+
+    let Some(rec) = &model.recorder.recording else {
+        return;
+    };
+
+    let keep = rec.frames_seen % decimation == 0;
+
+    if keep {
+        let window = app.window(model.main_wid).unwrap();
+        let device = window.device();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("recorder encoder"),
+        });
+
+        encode_offscreen(app, model, &rec.target, app.time, &mut encoder);
+
+        let snapshot = rec
+            .capturer
+            .capture(device, &mut encoder, &rec.target.texture);
+        window.queue().submit(Some(encoder.finish()));
+
+        let index = rec.frames_kept;
+        let sender = rec.frame_sender.clone();
+        let dropped = rec.frames_dropped.clone();
+        let read = snapshot.read(move |result| match result {
+            Ok(image) => match sender.try_send((index, image.to_owned())) {
+                Err(TrySendError::Full(_)) => {
+                    dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                // The writer only hangs up after an error, which is reported when it's joined
+                Ok(()) | Err(TrySendError::Disconnected(_)) => (),
+            },
+            Err(error) => {
+                println!("Error capturing frame {index}: {error:?}");
+                dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        // The writer skips the missing index once later frames arrive
+        if let Err(error) = read {
+            println!("Error capturing frame {index}: {error:?}");
+            rec.frames_dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let Some(rec) = &mut model.recorder.recording else {
+        return;
+    };
+
+    rec.frames_seen += 1;
+    rec.frames_kept += keep as u32;
+
+    if rec.max_frames.is_some_and(|max| rec.frames_kept >= max) {
+        stop_recording(app, model);
+    }
+}
+
+// Waits for the frames still on the GPU, the writer encodes the rest in the background
+pub(crate) fn stop_recording(app: &App, model: &mut Model) {
+    let Some(Recording {
+        path,
+        capturer,
+        frames_kept,
+        frame_sender,
+        frames_dropped,
+        writer,
+        ..
+    }) = model.recorder.recording.take()
+    else {
+        return;
+    };
+
+    let window = app.window(model.main_wid).unwrap();
+
+    if let Err(error) = capturer.await_active_snapshots(window.device()) {
+        println!("Error capturing frames: {error:?}");
+    }
+
+    // Closes the channel once the capturer's copies are gone too, which ends the writer's loop
+    drop(capturer);
+    drop(frame_sender);
+
+    println!("Finishing {}", path.display());
+
+    model.recorder.finishing.push(FinishingRecording {
+        path,
+        frames_kept,
+        frames_dropped,
+        writer,
+    });
+}
+
+// Joins the writers that are done encoding, without waiting on the others
+fn report_finished_recordings(model: &mut Model) {
+    let finishing = &mut model.recorder.finishing;
+
+    while let Some(i) = finishing.iter().position(|f| f.writer.is_finished()) {
+        let FinishingRecording {
+            path,
+            frames_kept,
+            frames_dropped,
+            writer,
+        } = finishing.swap_remove(i);
+        let dropped = frames_dropped.load(Ordering::Relaxed);
+
+        match writer.join() {
+            Ok(Ok(())) if dropped > 0 => println!(
+                "Recorded {} frames to {}, {dropped} dropped as the writer fell behind",
+                frames_kept - dropped,
+                path.display()
+            ),
+            Ok(Ok(())) => println!("Recorded {frames_kept} frames to {}", path.display()),
+            Ok(Err(error)) => println!("Error writing {}: {error}", path.display()),
+            Err(_) => println!("Video writer for {} panicked", path.display()),
+        }
+    }
+}
+
+pub(crate) fn toggle_recording(app: &App, model: &mut Model) {
+    if model.recorder.recording.is_some() {
+        stop_recording(app, model);
+    } else {
+        start_recording(app, model);
+    }
+}
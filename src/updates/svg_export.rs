@@ -0,0 +1,206 @@
+use std::{
+    error::Error,
+    f32::consts::TAU,
+    fmt::Write as _,
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use nannou::App;
+
+use crate::{
+    AgentSnapshot, GlyphShape, GlyphUnits, LeaderMode, Model, LEADER_ID, MAX_SCREEN_X,
+    MAX_SCREEN_Y, MIN_SCREEN_X, MIN_SCREEN_Y,
+};
+
+const BOID_COLOR: &str = "#ffffff";
+const LEADER_COLOR: &str = "#33b3ff";
+const PURSUIT_COLOR: &str = "#ffff66";
+const BOUNDARY_COLOR: &str = "#5a5a5a";
+
+// Outline of each shape in in_glyph from glyphs.wgsl, +y along the heading and spanning -1 -> 1
+fn glyph_outline(shape: GlyphShape) -> &'static str {
+    match shape {
+        GlyphShape::Arrow => r#"<path d="M0 1 L0.65 -1 L0 -0.45 L-0.65 -1 Z"/>"#,
+        GlyphShape::Chevron => {
+            r#"<path d="M-0.7 -0.8 L0 1 L0.7 -0.8" fill="none" stroke="currentColor" stroke-width="0.44" stroke-linecap="round" stroke-linejoin="round"/>"#
+        }
+        GlyphShape::Fish => {
+            r#"<ellipse cx="0" cy="0.25" rx="0.42" ry="0.75"/><path d="M0 -0.4 L0.5 -1 L0 -0.75 L-0.5 -1 Z"/>"#
+        }
+    }
+}
+
+fn glyph_shape(shape: u32) -> GlyphShape {
+    GlyphShape::ALL
+        .into_iter()
+        .find(|s| *s as u32 == shape)
+        .unwrap_or(GlyphShape::Arrow)
+}
+
+// Predators are drawn with palette(i + 12.0) * 4.5 in boids_frag.wgsl
+fn predator_color(i: usize) -> String {
+    let a = [0.120, 0.618, 0.624];
+    let b = [0.878, 0.214, 0.229];
+    let c = [0.654, 0.772, 0.426];
+    let d = [0.937, 0.190, 0.152];
+    let t = i as f32 + 12.0;
+
+    let [red, green, blue] = [0, 1, 2].map(|k| {
+        let v = 4.5 * a[k] * b[k] * (TAU * (c[k] * t + d[k])).cos();
+        (v.clamp(0.0, 1.0) * 255.0) as u8
+    });
+
+    format!("#{red:02x}{green:02x}{blue:02x}")
+}
+
+// Places a glyph from <defs> at the agent, scaled to size pixels and turned to its velocity
+fn glyph_use(svg: &mut String, id: &str, pos: [f32; 2], vel: [f32; 2], size: f32, color: &str) {
+    // Agents that have come to a stop keep pointing up, as in glyph()
+    let angle = if vel[0].hypot(vel[1]) > 0.00001 {
+        vel[1].atan2(vel[0]).to_degrees() - 90.0
+    } else {
+        0.0
+    };
+
+    // The y flip in scale turns the y up glyph frame into svg's y down one
+    let _ = writeln!(
+        svg,
+        r##"<use href="#{id}" fill="{color}" color="{color}" transform="translate({:.3} {:.3}) scale({size:.4} {:.4}) rotate({angle:.2})"/>"##,
+        pos[0], pos[1], -size
+    );
+}
+
+// Writes what the window currently shows as an svg, one unit per framebuffer pixel
+pub(crate) fn export_svg(app: &App, model: &Model) -> Result<PathBuf, Box<dyn Error>> {
+    if model.comparison.split.is_some() {
+        return Err("unavailable in split view, end the comparison first".into());
+    }
+
+    let AgentSnapshot {
+        boids,
+        predators,
+        pursuits,
+        captures,
+        captured,
+        ..
+    } = &model.agents;
+
+    let export = &model.svg_export;
+    let vp = &model.variables.view_params;
+    let gp = &model.variables.glyph_params;
+    let resolution = model.uniforms.resolution;
+    let [width, height] = resolution.xy;
+    let longest = f32::max(width, height);
+
+    // Inverse of world_pos in view.wgsl
+    let to_svg = |p: [f32; 2]| {
+        [
+            ((p[0] - vp.x_shift) * vp.zoom * longest + width) / 2.0,
+            (height - (p[1] - vp.y_shift) * vp.zoom * longest) / 2.0,
+        ]
+    };
+    let world_to_px = vp.zoom * longest / 2.0;
+    let glyph_px = |size: f32| {
+        if gp.units == GlyphUnits::Screen as u32 {
+            size * resolution.scale_factor
+        } else {
+            size * world_to_px
+        }
+    };
+    let line_width = 1.5 * resolution.scale_factor;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<defs><g id="boid">{}</g><g id="predator">{}</g></defs>"#,
+        glyph_outline(glyph_shape(gp.boid_shape)),
+        glyph_outline(glyph_shape(gp.predator_shape))
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="{width}" height="{height}" fill="#000000"/>"##
+    );
+
+    if export.boundary {
+        let [x0, y0] = to_svg([MIN_SCREEN_X, MAX_SCREEN_Y]);
+        let [x1, y1] = to_svg([MAX_SCREEN_X, MIN_SCREEN_Y]);
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{x0:.3}" y="{y0:.3}" width="{:.3}" height="{:.3}" fill="none" stroke="{BOUNDARY_COLOR}" stroke-width="{line_width}"/>"#,
+            x1 - x0,
+            y1 - y0
+        );
+    }
+
+    if export.pursuit_lines {
+        for (predator, &target) in predators.iter().zip(pursuits) {
+            let Some(boid) = boids.get(target as usize) else {
+                continue;
+            };
+            let [x1, y1] = to_svg(predator.pos);
+            let [x2, y2] = to_svg(boid.pos);
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{x1:.3}" y1="{y1:.3}" x2="{x2:.3}" y2="{y2:.3}" stroke="{PURSUIT_COLOR}" stroke-width="{line_width}"/>"#
+            );
+        }
+    }
+
+    let leader_on = model.sim.params.leader.mode != LeaderMode::Off as u32;
+
+    for (i, boid) in boids.iter().enumerate() {
+        if captured.get(i) == Some(&0.0) {
+            continue;
+        }
+
+        let color = if i == LEADER_ID && leader_on {
+            LEADER_COLOR
+        } else {
+            BOID_COLOR
+        };
+        glyph_use(
+            &mut svg,
+            "boid",
+            to_svg(boid.pos),
+            boid.vel,
+            glyph_px(gp.boid_size),
+            color,
+        );
+    }
+
+    for (i, predator) in predators.iter().enumerate() {
+        // Predators grow a little with every capture, as in the fragment shader
+        let growth = 1.0 + 0.025 * captures.get(i).copied().unwrap_or(0) as f32;
+        glyph_use(
+            &mut svg,
+            "predator",
+            to_svg(predator.pos),
+            predator.vel,
+            glyph_px(gp.predator_size) * growth,
+            &predator_color(i),
+        );
+    }
+
+    svg.push_str("</svg>\n");
+
+    let dir = app
+        .project_path()
+        .expect("failed to locate project directory")
+        .join(&export.output_dir);
+    fs::create_dir_all(&dir)?;
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = dir.join(format!("frame-{stamp}.svg"));
+    fs::write(&path, svg)?;
+
+    Ok(path)
+}
@@ -1,4 +1,4 @@
-use bytemuck::Pod;
+use bytemuck::{Pod, Zeroable};
 use futures::executor::block_on;
 use nannou::{
     event::Update,
@@ -6,18 +6,88 @@ use nannou::{
     App,
 };
 
+use super::{
+    comparison::{update_split_resolution, with_other_simulation},
+    follow_camera::update_follow_camera,
+    formations::load_formation_csv,
+    hot_reload::poll_shader_changes,
+    offscreen::update_frame_sequence,
+    recorder::update_recording,
+};
 use crate::{
-    keyboard_controls::print_gpu_data, ui::update_ui, Boid, BoidParams, Model, PredatorParams,
-    ViewParams, NUM_BOIDS, NUM_PREDATORS,
+    create_post_targets, create_trail_targets, initial_food_patches,
+    keyboard_controls::print_gpu_data, ui::update_ui, AgentSnapshot, Boid, BoidFormation,
+    BoidParams, ColorParams, DensityMode, DensityParams, FoodParams, ForageState, GlyphParams,
+    GridParams, LeaderControl, Model, OverlayParams, PostParams, PredatorParams, ResolutionUniform,
+    TrailParams, ViewParams, DENSITY_GRID_X, DENSITY_GRID_Y, NUM_BOIDS, NUM_PREDATORS,
 };
 
 pub(crate) fn update(a: &App, m: &mut Model, u: Update) {
-    update_predator_props(a, m, u);
-    update_boid_props(a, m, u);
+    update_resolution(a, m);
+    poll_shader_changes(a, m);
+
+    // A running frame sequence steps the simulation itself, once per rendered frame
+    if m.frame_sequence.run.is_some() {
+        update_frame_sequence(a, m, u);
+    } else {
+        step_simulations(a, m, u);
+    }
+
+    update_recording(a, m);
     update_cpu_read_buffers(a, m, u);
+    update_follow_camera(a, m, u);
     update_ui::<[u32; NUM_PREDATORS]>(a, m, u);
 }
 
+// Follows the framebuffer through resizes, fullscreen and DPI changes. The trail textures are
+// per pixel so they are recreated at the new size, which drops the current trails.
+pub(crate) fn update_resolution(app: &App, model: &mut Model) {
+    let window = app.window(model.main_wid).unwrap();
+    let (width, height) = window.inner_size_pixels();
+    let scale_factor = window.scale_factor();
+    let current = model.uniforms.resolution;
+
+    if current.xy == [width as f32, height as f32] && current.scale_factor == scale_factor {
+        return;
+    }
+
+    // Minimised windows report a zero size, keep the last one until they come back
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let new_resolution = ResolutionUniform {
+        xy: [width as f32, height as f32],
+        origin: [0.0, 0.0],
+        scale_factor,
+        _padding: 0.0,
+    };
+
+    window.queue().write_buffer(
+        &model.uniforms.resolution_uniform,
+        0,
+        bytemuck::cast_slice(&[new_resolution]),
+    );
+    model.uniforms.resolution = new_resolution;
+
+    model.trails.targets = create_trail_targets(
+        window.device(),
+        &model.layouts,
+        &model.trails.params_uniform,
+        [width, height],
+    );
+    model.post.targets = create_post_targets(
+        window.device(),
+        &model.layouts,
+        &model.post.params_uniform,
+        &model.post.sampler,
+        [width, height],
+        window.msaa_samples(),
+    );
+
+    update_split_resolution(app, model);
+}
+
 pub(crate) fn update_view_params_buffer(app: &App, model: &mut Model) {
     let window = app.window(model.main_wid).unwrap();
     let queue = window.queue();
@@ -40,20 +110,36 @@ pub(crate) fn update_boid_params_buffer(app: &App, model: &mut Model) {
     let window = app.window(model.main_wid).unwrap();
     let queue = window.queue();
 
+    // clamp() in the shaders is undefined when the lower bound is above the upper one
+    let bp = &mut model.sim.params.boid_params;
+    bp.min_speed = bp.min_speed.min(bp.max_speed);
+    bp.min_velocity = bp.min_velocity.min(bp.max_velocity);
+
     let new_boid_params = BoidParams {
-        max_velocity: model.variables.boid_params.max_velocity,
-        min_velocity: model.variables.boid_params.min_velocity,
-        turn_factor: model.variables.boid_params.turn_factor,
-        visual_range: model.variables.boid_params.visual_range,
-        protected_range: model.variables.boid_params.protected_range,
-        centering_factor: model.variables.boid_params.centering_factor,
-        self_avoid_factor: model.variables.boid_params.self_avoid_factor,
-        predator_avoid_factor: model.variables.boid_params.predator_avoid_factor,
-        matching_factor: model.variables.boid_params.matching_factor,
+        max_velocity: model.sim.params.boid_params.max_velocity,
+        min_velocity: model.sim.params.boid_params.min_velocity,
+        turn_factor: model.sim.params.boid_params.turn_factor,
+        visual_range: model.sim.params.boid_params.visual_range,
+        protected_range: model.sim.params.boid_params.protected_range,
+        centering_factor: model.sim.params.boid_params.centering_factor,
+        self_avoid_factor: model.sim.params.boid_params.self_avoid_factor,
+        predator_avoid_factor: model.sim.params.boid_params.predator_avoid_factor,
+        matching_factor: model.sim.params.boid_params.matching_factor,
+        alarm_spread: model.sim.params.boid_params.alarm_spread,
+        alarm_delay: model.sim.params.boid_params.alarm_delay,
+        alarm_decay: model.sim.params.boid_params.alarm_decay,
+        alarm_speed_boost: model.sim.params.boid_params.alarm_speed_boost,
+        alarm_separation_boost: model.sim.params.boid_params.alarm_separation_boost,
+        min_speed: model.sim.params.boid_params.min_speed,
+        max_speed: model.sim.params.boid_params.max_speed,
+        max_force: model.sim.params.boid_params.max_force,
+        limit_mode: model.sim.params.boid_params.limit_mode,
+        wander_amplitude: model.sim.params.boid_params.wander_amplitude,
+        wander_correlation: model.sim.params.boid_params.wander_correlation,
     };
 
     queue.write_buffer(
-        &model.variables.boid_params_storage,
+        &model.sim.boid_params_storage,
         0,
         bytemuck::cast_slice(&[new_boid_params]),
     );
@@ -63,26 +149,337 @@ pub(crate) fn update_predator_params_buffer(app: &App, model: &mut Model) {
     let window = app.window(model.main_wid).unwrap();
     let queue = window.queue();
 
+    // Same bounds as update_boid_params_buffer
+    let pp = &mut model.sim.params.predator_params;
+    pp.min_speed = pp.min_speed.min(pp.max_speed);
+    pp.min_velocity = pp.min_velocity.min(pp.max_velocity);
+
     let new_pred_params = PredatorParams {
-        max_velocity: model.variables.predator_params.max_velocity,
-        min_velocity: model.variables.predator_params.min_velocity,
-        turn_factor: model.variables.predator_params.turn_factor,
-        pursuit_factor: model.variables.predator_params.pursuit_factor,
-        pursuit_multiplier: model.variables.predator_params.pursuit_multiplier,
-        matching_factor: model.variables.predator_params.matching_factor,
-        self_avoid_factor: model.variables.predator_params.self_avoid_factor,
-        visual_range: model.variables.predator_params.visual_range,
-        protected_range: model.variables.predator_params.protected_range,
-        interest_range: model.variables.predator_params.interest_range,
+        max_velocity: model.sim.params.predator_params.max_velocity,
+        min_velocity: model.sim.params.predator_params.min_velocity,
+        turn_factor: model.sim.params.predator_params.turn_factor,
+        pursuit_factor: model.sim.params.predator_params.pursuit_factor,
+        pursuit_multiplier: model.sim.params.predator_params.pursuit_multiplier,
+        matching_factor: model.sim.params.predator_params.matching_factor,
+        self_avoid_factor: model.sim.params.predator_params.self_avoid_factor,
+        visual_range: model.sim.params.predator_params.visual_range,
+        protected_range: model.sim.params.predator_params.protected_range,
+        interest_range: model.sim.params.predator_params.interest_range,
+        min_speed: model.sim.params.predator_params.min_speed,
+        max_speed: model.sim.params.predator_params.max_speed,
+        max_force: model.sim.params.predator_params.max_force,
+        limit_mode: model.sim.params.predator_params.limit_mode,
     };
 
     queue.write_buffer(
-        &model.variables.predator_params_storage,
+        &model.sim.predator_params_storage,
         0,
         bytemuck::cast_slice(&[new_pred_params]),
     );
 }
 
+pub(crate) fn update_food_params_buffer(app: &App, model: &mut Model) {
+    let window = app.window(model.main_wid).unwrap();
+    let queue = window.queue();
+
+    let new_food_params = FoodParams {
+        hunger_rate: model.sim.params.food_params.hunger_rate,
+        hunger_threshold: model.sim.params.food_params.hunger_threshold,
+        foraging_factor: model.sim.params.food_params.foraging_factor,
+        eat_rate: model.sim.params.food_params.eat_rate,
+        regrow_rate: model.sim.params.food_params.regrow_rate,
+        max_food: model.sim.params.food_params.max_food,
+    };
+
+    queue.write_buffer(
+        &model.sim.food_params_storage,
+        0,
+        bytemuck::cast_slice(&[new_food_params]),
+    );
+}
+
+pub(crate) fn update_leader_buffer(app: &App, model: &mut Model) {
+    let window = app.window(model.main_wid).unwrap();
+    let queue = window.queue();
+
+    let new_leader = LeaderControl {
+        target_pos: model.sim.params.leader.target_pos,
+        influence: model.sim.params.leader.influence,
+        mode: model.sim.params.leader.mode,
+    };
+
+    queue.write_buffer(
+        &model.sim.leader_storage,
+        0,
+        bytemuck::cast_slice(&[new_leader]),
+    );
+}
+
+pub(crate) fn update_glyph_params_buffer(app: &App, model: &mut Model) {
+    let window = app.window(model.main_wid).unwrap();
+    let queue = window.queue();
+
+    let new_glyph_params = GlyphParams {
+        boid_size: model.variables.glyph_params.boid_size,
+        predator_size: model.variables.glyph_params.predator_size,
+        boid_shape: model.variables.glyph_params.boid_shape,
+        predator_shape: model.variables.glyph_params.predator_shape,
+        units: model.variables.glyph_params.units,
+    };
+
+    queue.write_buffer(
+        &model.variables.glyph_params_uniform,
+        0,
+        bytemuck::cast_slice(&[new_glyph_params]),
+    );
+}
+
+pub(crate) fn update_color_params_buffer(app: &App, model: &mut Model) {
+    let window = app.window(model.main_wid).unwrap();
+    let queue = window.queue();
+    let [offset, amplitude, frequency, phase] = model.variables.color_map.coefficients();
+
+    let new_color_params = ColorParams {
+        offset,
+        amplitude,
+        frequency,
+        phase,
+        mode: model.variables.color_params.mode,
+        min_value: model.variables.color_params.min_value,
+        max_value: model.variables.color_params.max_value,
+        _padding: 0.0,
+    };
+
+    model.variables.color_params = new_color_params;
+
+    queue.write_buffer(
+        &model.variables.color_params_uniform,
+        0,
+        bytemuck::cast_slice(&[new_color_params]),
+    );
+}
+
+pub(crate) fn update_density_params_buffer(app: &App, model: &mut Model) {
+    let window = app.window(model.main_wid).unwrap();
+    let queue = window.queue();
+
+    let new_density_params = DensityParams {
+        mode: model.variables.density_params.mode,
+        kernel_radius: model.variables.density_params.kernel_radius,
+        average_frames: model.variables.density_params.average_frames,
+        show_average: model.variables.density_params.show_average,
+        max_density: model.variables.density_params.max_density,
+        opacity: model.variables.density_params.opacity,
+    };
+
+    queue.write_buffer(
+        &model.variables.density_params_uniform,
+        0,
+        bytemuck::cast_slice(&[new_density_params]),
+    );
+}
+
+pub(crate) fn update_overlay_params_buffer(app: &App, model: &mut Model) {
+    let window = app.window(model.main_wid).unwrap();
+    let queue = window.queue();
+
+    let new_overlay_params = OverlayParams {
+        predator_ranges: model.variables.overlay_params.predator_ranges,
+        pursuit_lines: model.variables.overlay_params.pursuit_lines,
+        strategy_targets: model.variables.overlay_params.strategy_targets,
+        boid_ranges: model.variables.overlay_params.boid_ranges,
+        boid_id: model.variables.overlay_params.boid_id,
+        line_width: model.variables.overlay_params.line_width,
+        selection: model.variables.overlay_params.selection,
+        selected_id: model.variables.overlay_params.selected_id,
+    };
+
+    queue.write_buffer(
+        &model.variables.overlay_params_uniform,
+        0,
+        bytemuck::cast_slice(&[new_overlay_params]),
+    );
+}
+
+pub(crate) fn update_grid_params_buffer(app: &App, model: &mut Model) {
+    let window = app.window(model.main_wid).unwrap();
+    let queue = window.queue();
+
+    let new_grid_params = GridParams {
+        grid: model.variables.grid_params.grid,
+        axes: model.variables.grid_params.axes,
+        boundary: model.variables.grid_params.boundary,
+        scale_bar: model.variables.grid_params.scale_bar,
+        min_spacing: model.variables.grid_params.min_spacing,
+        opacity: model.variables.grid_params.opacity,
+    };
+
+    queue.write_buffer(
+        &model.variables.grid_params_uniform,
+        0,
+        bytemuck::cast_slice(&[new_grid_params]),
+    );
+}
+
+// Forgets the long-term average as well as the current density
+pub(crate) fn clear_density(app: &App, model: &mut Model) {
+    let window = app.window(model.main_wid).unwrap();
+    let queue = window.queue();
+
+    queue.write_buffer(
+        &model.sim.buffers.density_buf,
+        0,
+        bytemuck::cast_slice(&vec![[0.0f32; 2]; DENSITY_GRID_X * DENSITY_GRID_Y]),
+    );
+}
+
+pub(crate) fn update_trail_params_buffer(app: &App, model: &mut Model) {
+    let window = app.window(model.main_wid).unwrap();
+    let queue = window.queue();
+
+    let new_trail_params = TrailParams {
+        boid_color: model.trails.params.boid_color,
+        predator_color: model.trails.params.predator_color,
+        boid_length: model.trails.params.boid_length,
+        predator_length: model.trails.params.predator_length,
+        enabled: model.trails.params.enabled,
+        _padding: 0.0,
+    };
+
+    queue.write_buffer(
+        &model.trails.params_uniform,
+        0,
+        bytemuck::cast_slice(&[new_trail_params]),
+    );
+}
+
+pub(crate) fn update_post_params_buffer(app: &App, model: &mut Model) {
+    let window = app.window(model.main_wid).unwrap();
+    let queue = window.queue();
+
+    let new_post_params = PostParams {
+        bloom: model.post.params.bloom,
+        bloom_threshold: model.post.params.bloom_threshold,
+        bloom_intensity: model.post.params.bloom_intensity,
+        bloom_radius: model.post.params.bloom_radius,
+        tone_mapping: model.post.params.tone_mapping,
+        exposure: model.post.params.exposure,
+        vignette: model.post.params.vignette,
+        vignette_strength: model.post.params.vignette_strength,
+        vignette_radius: model.post.params.vignette_radius,
+    };
+
+    queue.write_buffer(
+        &model.post.params_uniform,
+        0,
+        bytemuck::cast_slice(&[new_post_params]),
+    );
+}
+
+// Re-runs the init pipelines with the current seed and clears all per-run state. While comparing
+// both simulations are reset, so they start from the same state.
+pub(crate) fn reset_simulation(app: &App, model: &mut Model) {
+    reset_current_simulation(app, model);
+    with_other_simulation(model, |m| reset_current_simulation(app, m));
+}
+
+fn reset_current_simulation(app: &App, model: &mut Model) {
+    let mw = app.window(model.main_wid).unwrap();
+    let device = mw.device();
+    let queue = mw.queue();
+
+    queue.write_buffer(
+        &model.uniforms.seed_uniform,
+        0,
+        bytemuck::cast_slice(&[model.variables.seed]),
+    );
+    queue.write_buffer(
+        &model.uniforms.init_params_uniform,
+        0,
+        bytemuck::cast_slice(&[model.variables.init_params]),
+    );
+    queue.write_buffer(
+        &model.sim.buffers.pursuits_buf,
+        0,
+        bytemuck::cast_slice(&[0xFFFFFFFFu32; NUM_PREDATORS]),
+    );
+    queue.write_buffer(
+        &model.sim.buffers.captures_buf,
+        0,
+        bytemuck::cast_slice(&[0u32; NUM_PREDATORS]),
+    );
+    queue.write_buffer(
+        &model.sim.buffers.captured_boids_buf,
+        0,
+        bytemuck::cast_slice(&[1.0f32; NUM_BOIDS]),
+    );
+    queue.write_buffer(
+        &model.sim.buffers.alarm_buf,
+        0,
+        bytemuck::cast_slice(&[0.0f32; 2 * NUM_BOIDS]),
+    );
+    queue.write_buffer(
+        &model.sim.buffers.forage_buf,
+        0,
+        bytemuck::cast_slice(&[ForageState::zeroed(); NUM_BOIDS]),
+    );
+    queue.write_buffer(
+        &model.sim.buffers.food_buf,
+        0,
+        bytemuck::cast_slice(&initial_food_patches(model.sim.params.food_params.max_food)),
+    );
+
+    clear_density(app, model);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("reset_simulation encoder - Boids"),
+    });
+
+    {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Compute Pass - Boids"),
+        });
+        compute_pass.set_pipeline(&model.pipelines.compute_boid_pos_pipeline);
+        compute_pass.set_bind_group(0, &model.sim.compute_bind_group, &[]);
+        compute_pass.dispatch_workgroups(16, 16, 1);
+    }
+
+    queue.submit(Some(encoder.finish()));
+
+    // Boids past the end of the file keep their rectangle positions
+    if model.variables.init_params.boid_formation == BoidFormation::Csv as u32 {
+        let csv_path = app
+            .project_path()
+            .expect("failed to locate project directory")
+            .join(&model.controls.formation_csv);
+
+        match load_formation_csv(&csv_path) {
+            Ok(boids) => queue.write_buffer(
+                &model.sim.buffers.boids_pos_buf,
+                0,
+                bytemuck::cast_slice(&boids),
+            ),
+            Err(error) => println!("Error loading boid formation: {error}"),
+        }
+    }
+
+    // Predators are placed once the flock is in position
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("reset_simulation encoder - Predator"),
+    });
+
+    {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Compute Pass - Predator"),
+        });
+        compute_pass.set_pipeline(&model.pipelines.compute_predator_pos_pipeline);
+        compute_pass.set_bind_group(0, &model.sim.compute_bind_group, &[]);
+        compute_pass.dispatch_workgroups(4, 1, 1);
+    }
+
+    queue.submit(Some(encoder.finish()));
+}
+
 pub(crate) fn update_cpu_read_buffers(app: &App, model: &mut Model, _update: Update) {
     let mw = app.window(model.main_wid).unwrap();
     let device = mw.device();
@@ -93,38 +490,117 @@ pub(crate) fn update_cpu_read_buffers(app: &App, model: &mut Model, _update: Upd
     });
 
     encoder.copy_buffer_to_buffer(
-        &model.buffers.boids_pos_buf,
+        &model.sim.buffers.boids_pos_buf,
         0,
-        &model.buffers.cpu_read_boids_pos_buf,
+        &model.sim.buffers.cpu_read_boids_pos_buf,
         0,
         (std::mem::size_of::<[Boid; NUM_BOIDS]>()) as wgpu::BufferAddress,
     );
 
     encoder.copy_buffer_to_buffer(
-        &model.buffers.predator_pos_buf,
+        &model.sim.buffers.predator_pos_buf,
         0,
-        &model.buffers.cpu_read_predators_pos_buf,
+        &model.sim.buffers.cpu_read_predators_pos_buf,
         0,
         (std::mem::size_of::<[Boid; NUM_PREDATORS]>()) as wgpu::BufferAddress,
     );
 
     encoder.copy_buffer_to_buffer(
-        &model.buffers.pursuits_buf,
+        &model.sim.buffers.pursuits_buf,
         0,
-        &model.buffers.cpu_read_predators_pursuits_buf,
+        &model.sim.buffers.cpu_read_predators_pursuits_buf,
         0,
         (std::mem::size_of::<[u32; NUM_PREDATORS]>()) as wgpu::BufferAddress,
     );
 
     encoder.copy_buffer_to_buffer(
-        &model.buffers.captures_buf,
+        &model.sim.buffers.captures_buf,
         0,
-        &model.buffers.cpu_read_predators_captures_buf,
+        &model.sim.buffers.cpu_read_predators_captures_buf,
         0,
         (std::mem::size_of::<[u32; NUM_PREDATORS]>()) as wgpu::BufferAddress,
     );
 
+    encoder.copy_buffer_to_buffer(
+        &model.sim.buffers.captured_boids_buf,
+        0,
+        &model.sim.buffers.cpu_read_captured_boids_buf,
+        0,
+        (std::mem::size_of::<[f32; NUM_BOIDS]>()) as wgpu::BufferAddress,
+    );
+
+    encoder.copy_buffer_to_buffer(
+        &model.sim.buffers.neighbours_buf,
+        0,
+        &model.sim.buffers.cpu_read_neighbours_buf,
+        0,
+        (std::mem::size_of::<[u32; NUM_BOIDS]>()) as wgpu::BufferAddress,
+    );
+
     queue.submit(Some(encoder.finish()));
+
+    // Every buffer is mapped before a single wait, so the frame stalls on the gpu only once
+    let buffers = &model.sim.buffers;
+    let reads = [
+        &buffers.cpu_read_boids_pos_buf,
+        &buffers.cpu_read_predators_pos_buf,
+        &buffers.cpu_read_predators_pursuits_buf,
+        &buffers.cpu_read_predators_captures_buf,
+        &buffers.cpu_read_captured_boids_buf,
+        &buffers.cpu_read_neighbours_buf,
+    ];
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    for (i, buffer) in reads.iter().enumerate() {
+        let tx = tx.clone();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = tx.send((i, result));
+            });
+    }
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let mut mapped = vec![false; reads.len()];
+    let mut failure = None;
+
+    for (i, result) in rx.try_iter() {
+        match result {
+            Ok(()) => mapped[i] = true,
+            Err(error) => failure = Some(error),
+        }
+    }
+
+    // Keeps the last snapshot, the ui and cameras just see a frame old agents
+    if let Some(error) = failure {
+        println!("Error reading back agents: {error}");
+        for (buffer, _) in reads.iter().zip(&mapped).filter(|(_, mapped)| **mapped) {
+            buffer.unmap();
+        }
+        return;
+    }
+
+    let snapshot = AgentSnapshot {
+        boids: read_mapped(reads[0]),
+        predators: read_mapped(reads[1]),
+        pursuits: read_mapped(reads[2]),
+        captures: read_mapped(reads[3]),
+        captured: read_mapped(reads[4]),
+        neighbours: read_mapped(reads[5]),
+    };
+    model.agents = snapshot;
+}
+
+// Advances Model.sim and, while comparing, the other simulation by one step
+pub(crate) fn step_simulations(app: &App, model: &mut Model, update: Update) {
+    update_predator_props(app, model, update);
+    update_boid_props(app, model, update);
+
+    with_other_simulation(model, |m| {
+        update_predator_props(app, m, update);
+        update_boid_props(app, m, update);
+    });
 }
 
 pub(crate) fn update_boid_props(app: &App, model: &mut Model, _update: Update) {
@@ -136,14 +612,56 @@ pub(crate) fn update_boid_props(app: &App, model: &mut Model, _update: Update) {
         label: Some("update_boid_props encoder"),
     });
 
+    // Its own pass so every boid reads the same step's alarm levels before any are replaced
+    {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Boids Alarm Compute Pass"),
+        });
+        compute_pass.set_pipeline(&model.pipelines.compute_alarm_pipeline);
+        compute_pass.set_bind_group(0, &model.sim.compute_bind_group, &[]);
+        compute_pass.set_bind_group(1, &model.uniforms.uniform_bind_group, &[]);
+        compute_pass.dispatch_workgroups(NUM_BOIDS.div_ceil(64) as u32, 1, 1);
+    }
+
+    {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Food Regrowth Compute Pass"),
+        });
+        compute_pass.set_pipeline(&model.pipelines.compute_food_pipeline);
+        compute_pass.set_bind_group(0, &model.sim.compute_bind_group, &[]);
+        compute_pass.dispatch_workgroups(1, 1, 1);
+    }
+
+    {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Boids Forage Compute Pass"),
+        });
+        compute_pass.set_pipeline(&model.pipelines.compute_forage_pipeline);
+        compute_pass.set_bind_group(0, &model.sim.compute_bind_group, &[]);
+        compute_pass.dispatch_workgroups(NUM_BOIDS.div_ceil(64) as u32, 1, 1);
+    }
+
     {
         let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("Boids SAC Compute Pass"),
         });
         compute_pass.set_pipeline(&model.pipelines.compute_sac_pipeline);
-        compute_pass.set_bind_group(0, &model.compute_bind_group, &[]);
+        compute_pass.set_bind_group(0, &model.sim.compute_bind_group, &[]);
         compute_pass.set_bind_group(1, &model.uniforms.uniform_bind_group, &[]);
-        compute_pass.dispatch_workgroups(16, 16, 1); // Adjust workgroup size as needed
+        compute_pass.dispatch_workgroups(NUM_BOIDS.div_ceil(64) as u32, 1, 1);
+    }
+
+    if model.variables.density_params.mode != DensityMode::Off as u32 {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Density Splat Compute Pass"),
+        });
+        compute_pass.set_pipeline(&model.pipelines.compute_density_pipeline);
+        compute_pass.set_bind_group(0, &model.sim.compute_bind_group, &[]);
+        compute_pass.dispatch_workgroups(
+            DENSITY_GRID_X.div_ceil(8) as u32,
+            DENSITY_GRID_Y.div_ceil(8) as u32,
+            1,
+        );
     }
 
     queue.submit(Some(encoder.finish()));
@@ -163,7 +681,7 @@ pub(crate) fn update_predator_props(app: &App, model: &mut Model, _update: Updat
             label: Some("Predator Compute Pass"),
         });
         compute_pass.set_pipeline(&model.pipelines.compute_pursuit_curve_pipeline);
-        compute_pass.set_bind_group(0, &model.compute_bind_group, &[]);
+        compute_pass.set_bind_group(0, &model.sim.compute_bind_group, &[]);
         compute_pass.set_bind_group(1, &model.uniforms.uniform_bind_group, &[]);
         compute_pass.dispatch_workgroups(4, 1, 1); // Adjust workgroup size as needed
     }
@@ -171,3 +689,12 @@ pub(crate) fn update_predator_props(app: &App, model: &mut Model, _update: Updat
     // println!("computing new predator pos!");
     queue.submit(Some(encoder.finish()));
 }
+
+// Copies out of a buffer update_cpu_read_buffers has mapped, and unmaps it for the next copy
+fn read_mapped<T: Pod>(buffer: &Buffer) -> Vec<T> {
+    let view = buffer.slice(..).get_mapped_range();
+    let data = bytemuck::cast_slice(&view).to_vec();
+    drop(view);
+    buffer.unmap();
+    data
+}